@@ -0,0 +1,374 @@
+//! Search latency over a generated corpus.
+//!
+//! Needs the PostgreSQL server of `configuration.yaml`. A temporary database is created, loaded
+//! with a generated corpus and dropped at the end of the run.
+//!
+//! The `search` group runs the whole search. The `ranking` group compares the cosine ranking of the
+//! queries, streaming their postings (`streamed`), with the cosine ranking as it was before the
+//! postings were streamed, loading the whole posting list of each query word (`loaded`).
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use sqlx::{Connection, Executor, PgConnection, PgPool};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use uuid::Uuid;
+use warcse::analysis::{get_analyzer, AnalyzerKind};
+use warcse::configuration::{get_configuration, FieldBoosts, RankingModel, SearchSettings};
+use warcse::database::MIGRATOR;
+use warcse::domain::ranking::get_ranker;
+use warcse::domain::{select_page, Hit, Page};
+use warcse::services::{
+    calculate_query_weights, get_corpus_tf_idf, ingest_corpus, perform_search, score_postings,
+    QueryWord, SearchOptions,
+};
+
+/// The number of generated records.
+const RECORDS: usize = 3_000;
+/// The number of words of each generated record.
+const RECORD_LENGTH: usize = 250;
+/// The size of the vocabulary.
+const VOCABULARY: usize = 20_000;
+/// The benchmarked queries: a rare word, a frequent one and a mix of both.
+const QUERIES: [&str; 3] = ["termhki", "termabe", "termabe termaci termahs termhki"];
+
+/// A small deterministic pseudo-random generator (64-bit LCG).
+struct Lcg(u64);
+
+impl Lcg {
+    /// Get a number in `[0, 1)`.
+    fn next(&mut self) -> f64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1_u64 << 53) as f64
+    }
+}
+
+/// Get the word of a vocabulary index (`termaaa`, `termaab`, ...).
+fn word(mut index: usize) -> String {
+    let mut letters = [b'a'; 3];
+    for letter in letters.iter_mut().rev() {
+        *letter = b'a' + (index % 26) as u8;
+        index /= 26;
+    }
+    format!("term{}", String::from_utf8_lossy(&letters))
+}
+
+/// Write a WARC file with Zipf-like distributed words.
+fn generate_corpus(path: &PathBuf) {
+    let mut rng = Lcg(42);
+    let mut out = BufWriter::new(File::create(path).expect("Failed to create the corpus."));
+    for i in 0..RECORDS {
+        let mut body = String::from("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\n");
+        for _ in 0..RECORD_LENGTH {
+            let index = (VOCABULARY as f64).powf(rng.next()) as usize - 1;
+            body.push_str(&word(index));
+            body.push(' ');
+        }
+        write!(
+            out,
+            "WARC/1.0\r\nWARC-Type: response\r\nWARC-TREC-ID: bench-{:05}\r\n\
+             WARC-Target-URI: http://bench.example.com/{}\r\nContent-Length: {}\r\n\r\n{}\r\n\r\n",
+            i,
+            i,
+            body.len(),
+            body
+        )
+        .expect("Failed to write the corpus.");
+    }
+}
+
+/// Create a database loaded with the generated corpus.
+///
+/// # Returns
+///
+/// * The pool, the name of the database and the connection string of the maintenance database.
+async fn setup() -> (PgPool, String, String) {
+    let mut configuration = get_configuration().expect("Failed to read configuration.");
+    configuration.database.database_name = Uuid::new_v4().to_string();
+    let maintenance_url = format!(
+        "{}/postgres",
+        configuration.database.connection_string_without_db()
+    );
+
+    let mut connection = PgConnection::connect(&maintenance_url)
+        .await
+        .expect("Failed to connect to Postgres.");
+    connection
+        .execute(
+            format!(
+                r#"CREATE DATABASE "{}""#,
+                configuration.database.database_name
+            )
+            .as_str(),
+        )
+        .await
+        .expect("Failed to create database.");
+    let pool = PgPool::connect(&configuration.database.connection_string())
+        .await
+        .expect("Failed to connect to Postgres.");
+    MIGRATOR
+        .run(&pool)
+        .await
+        .expect("Failed to migrate the database.");
+
+    let path = std::env::temp_dir().join(format!("{}.warc", configuration.database.database_name));
+    generate_corpus(&path);
+    ingest_corpus(
+        &pool,
+        "bench",
+        std::slice::from_ref(&path),
+        false,
+        configuration.analysis.analyzer,
+        configuration.analysis.max_body_size,
+    )
+    .await
+    .expect("Failed to load the corpus.");
+    std::fs::remove_file(&path).expect("Failed to remove the corpus.");
+
+    (pool, configuration.database.database_name, maintenance_url)
+}
+
+/// A posting, loaded along with the details of its record like the former `QueryWordRecordInfo`
+/// (the details are loaded, not used).
+#[allow(dead_code)]
+struct RecordPosting {
+    record: i32,
+    trec_id: String,
+    url: String,
+    corpus_name: String,
+    corpus_id: i32,
+    tf: f64,
+    idf: f64,
+    appearances: i32,
+    total_words: i32,
+}
+
+/// Get the query words of a query, with their query weights and corpus TF-IDF.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+/// * query     - The query.
+/// * analyzer  - The analyzer of the corpus.
+async fn query_words(pool: &PgPool, query: &str, analyzer: AnalyzerKind) -> Vec<QueryWord> {
+    let mut query_words: Vec<QueryWord> = get_analyzer(analyzer)
+        .analyze(query)
+        .into_iter()
+        .map(QueryWord::new)
+        .collect();
+    calculate_query_weights(&mut query_words, pool)
+        .await
+        .expect("Failed to search.");
+    get_corpus_tf_idf(&mut query_words, pool)
+        .await
+        .expect("Failed to search.");
+    query_words
+}
+
+/// Rank a query with the cosine ranking, streaming its postings and keeping the hits of the page
+/// only.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+/// * query     - The query.
+/// * analyzer  - The analyzer of the corpus.
+/// * page      - The requested page.
+async fn streamed(pool: &PgPool, query: &str, analyzer: AnalyzerKind, page: &Page) -> Vec<Hit> {
+    let query_words = query_words(pool, query, analyzer).await;
+    let ranker = get_ranker(RankingModel::Cosine, &SearchSettings::default());
+    let mut scorer = ranker.scorer(&query_words, None);
+    score_postings(&query_words, None, false, pool, |posting| {
+        scorer.add(&posting)
+    })
+    .await
+    .expect("Failed to search.");
+    select_page(scorer.hits(), page, |hit| (hit.score_mixed, hit.record)).1
+}
+
+/// The score of a record, as accumulated by the former cosine ranking.
+struct RecordRanking<'a> {
+    trec_id: &'a str,
+    corpus_id: i32,
+    x: f64,
+    y: f64,
+}
+
+/// Rank a query by loading everything, as the cosine ranking did before the postings were
+/// streamed.
+///
+/// The whole posting list of each query word is loaded with the details of its records, one query
+/// per word. The records are accumulated in a list searched for each posting, and all of them are
+/// sorted before the page is taken.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+/// * query     - The query.
+/// * analyzer  - The analyzer of the corpus.
+/// * page      - The requested page.
+async fn loaded(pool: &PgPool, query: &str, analyzer: AnalyzerKind, page: &Page) -> Vec<f64> {
+    let query_words = query_words(pool, query, analyzer).await;
+    let mut postings: Vec<Vec<RecordPosting>> = vec![];
+    for word in query_words.iter() {
+        postings.push(
+            sqlx::query_as!(
+                RecordPosting,
+                r#"
+                SELECT wri.record                  AS record,
+                       ri.trec_id                  AS trec_id,
+                       ri.uri                      AS url,
+                       ci.name                     AS corpus_name,
+                       ci.id                       AS corpus_id,
+                       wri.tf                      AS tf,
+                       wri.idf                     AS idf,
+                       wri.appearances             AS appearances,
+                       COALESCE(ri.total_words, 0) AS "total_words!"
+                FROM word_record_index wri
+                         JOIN record_index ri
+                              ON wri.record = ri.id
+                         JOIN corpus_info ci
+                              ON ri.corpus_id = ci.id
+                WHERE wri.word = $1
+                "#,
+                &word.word
+            )
+            .fetch_all(pool)
+            .await
+            .expect("Failed to search."),
+        );
+    }
+
+    // The query and corpus vectors.
+    let mut denominator: HashMap<i32, f64> = HashMap::new();
+    let mut corpora: Vec<(i32, f64, f64)> = vec![];
+    for word in query_words.iter() {
+        for corpus in word.word_corpus_tf_idf.iter() {
+            let weight = word.tfidf[&corpus.corpus_id];
+            let tf_idf = corpus.tf_idf.unwrap_or_default();
+            *denominator.entry(corpus.corpus_id).or_default() += weight.powi(2);
+            match corpora.iter_mut().find(|c| c.0 == corpus.corpus_id) {
+                Some(c) => {
+                    c.1 += weight * tf_idf;
+                    c.2 += tf_idf.powi(2);
+                }
+                None => corpora.push((corpus.corpus_id, weight * tf_idf, tf_idf.powi(2))),
+            }
+        }
+    }
+
+    // The record vectors.
+    let mut records: Vec<RecordRanking> = vec![];
+    for (word, postings) in query_words.iter().zip(postings.iter()) {
+        for posting in postings {
+            let weight = word.tfidf[&posting.corpus_id];
+            let tf_idf = posting.tf * posting.idf;
+            match records.iter_mut().find(|r| r.trec_id == posting.trec_id) {
+                Some(r) => {
+                    r.x += weight * tf_idf;
+                    r.y += tf_idf.powi(2);
+                }
+                None => records.push(RecordRanking {
+                    trec_id: &posting.trec_id,
+                    corpus_id: posting.corpus_id,
+                    x: weight * tf_idf,
+                    y: tf_idf.powi(2),
+                }),
+            }
+        }
+    }
+
+    let mut scores: Vec<f64> = records
+        .iter()
+        .map(|r| {
+            let norm = denominator[&r.corpus_id].sqrt();
+            let rank = r.x / (norm * r.y.sqrt());
+            let corpus_rank = corpora
+                .iter()
+                .find(|c| c.0 == r.corpus_id)
+                .map(|c| c.1 / (norm * c.2.sqrt()))
+                .unwrap_or_default();
+            (1_f64 - rank) * corpus_rank.log2()
+        })
+        .collect();
+    scores.sort_by(|a, b| b.total_cmp(a));
+    scores
+        .into_iter()
+        .skip(page.offset)
+        .take(page.limit)
+        .collect()
+}
+
+fn search(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create runtime.");
+    let (pool, database_name, maintenance_url) = runtime.block_on(setup());
+    let settings = SearchSettings::default();
+    let page = Page {
+        offset: 0,
+        limit: settings.default_limit,
+        after: None,
+    };
+
+    let mut group = c.benchmark_group("search");
+    for ranking in [RankingModel::Cosine, RankingModel::Bm25] {
+        let options = SearchOptions {
+            ranking,
+            expand: false,
+            feedback: false,
+            boosts: FieldBoosts::default(),
+            explain: false,
+        };
+        for query in QUERIES {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{:?}", ranking), query),
+                &query,
+                |b, query| {
+                    b.to_async(&runtime).iter(|| async {
+                        perform_search(&pool, query, &page, &options, &settings)
+                            .await
+                            .expect("Failed to search.")
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+
+    let analyzer = get_configuration()
+        .expect("Failed to read configuration.")
+        .analysis
+        .analyzer;
+    let mut group = c.benchmark_group("ranking");
+    for query in QUERIES {
+        group.bench_with_input(BenchmarkId::new("streamed", query), &query, |b, query| {
+            b.to_async(&runtime)
+                .iter(|| streamed(&pool, query, analyzer, &page))
+        });
+        group.bench_with_input(BenchmarkId::new("loaded", query), &query, |b, query| {
+            b.to_async(&runtime)
+                .iter(|| loaded(&pool, query, analyzer, &page))
+        });
+    }
+    group.finish();
+
+    runtime.block_on(async {
+        pool.close().await;
+        let mut connection = PgConnection::connect(&maintenance_url)
+            .await
+            .expect("Failed to connect to Postgres.");
+        connection
+            .execute(format!(r#"DROP DATABASE "{}" WITH (FORCE)"#, database_name).as_str())
+            .await
+            .expect("Failed to drop database.");
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = search
+}
+criterion_main!(benches);
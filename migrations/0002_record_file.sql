@@ -0,0 +1,8 @@
+-----------------------
+-- Table: RECORD_INDEX
+-----------------------
+-- The records indexed so far are not attached to any file.
+ALTER TABLE record_index
+    ADD COLUMN file_id INT REFERENCES corpus_files (id);
+
+COMMENT ON COLUMN record_index.file_id IS 'The file the record was read from.';
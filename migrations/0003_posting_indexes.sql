@@ -0,0 +1,16 @@
+-------------------------------
+-- Indexes: WORD_RECORD_INDEX
+-------------------------------
+-- The postings of the query words are read from the index only.
+CREATE INDEX word_record_index_word ON word_record_index (word) INCLUDE (record, appearances, tf, idf);
+CREATE INDEX word_record_index_record ON word_record_index (record);
+
+-------------------------------
+-- Indexes: WORD_CORPUS_INDEX
+-------------------------------
+CREATE INDEX word_corpus_index_word ON word_corpus_index (word);
+
+--------------------------
+-- Indexes: RECORD_INDEX
+--------------------------
+CREATE INDEX record_index_corpus_id ON record_index (corpus_id);
@@ -0,0 +1,7 @@
+----------------------------
+-- Table: WORD_RECORD_INDEX
+----------------------------
+ALTER TABLE word_record_index
+    ADD COLUMN positions INT[] NOT NULL DEFAULT '{}';
+
+COMMENT ON COLUMN word_record_index.positions IS 'The token positions of the word in the record (empty for records indexed before the positions were stored).';
@@ -0,0 +1,11 @@
+----------------------------
+-- Table: CORPUS_INFO
+----------------------------
+-- The corpora indexed so far were split on non-alphanumeric characters and lowercased.
+ALTER TABLE corpus_info
+    ADD COLUMN analyzer TEXT NOT NULL DEFAULT 'simple';
+
+ALTER TABLE corpus_info
+    ALTER COLUMN analyzer DROP DEFAULT;
+
+COMMENT ON COLUMN corpus_info.analyzer IS 'The analyzer of the records of the corpus, also used for the queries on it.';
@@ -0,0 +1,13 @@
+----------------------------
+-- Table: RECORD_TEXT
+----------------------------
+CREATE TABLE record_text
+(
+    record INT  NOT NULL REFERENCES record_index (id),
+    PRIMARY KEY (record),
+    text   TEXT NOT NULL
+);
+
+COMMENT ON TABLE record_text IS 'The text of the records, as analyzed by the indexer (used for the snippets).';
+COMMENT ON COLUMN record_text.record IS 'Foreign key to the linked record.';
+COMMENT ON COLUMN record_text.text IS 'The text of the record.';
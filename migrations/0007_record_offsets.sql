@@ -0,0 +1,9 @@
+----------------------------
+-- Table: RECORD_INDEX
+----------------------------
+ALTER TABLE record_index
+    ADD COLUMN file_offset   BIGINT,
+    ADD COLUMN member_offset BIGINT;
+
+COMMENT ON COLUMN record_index.file_offset IS 'The offset of the record in its file, or of the gzip member containing it (NULL for records indexed before the offsets were stored).';
+COMMENT ON COLUMN record_index.member_offset IS 'The offset of the record in the decompressed gzip member (0 in plain files).';
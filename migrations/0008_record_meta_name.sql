@@ -0,0 +1,7 @@
+----------------------------
+-- Table: RECORD_META
+----------------------------
+ALTER TABLE record_meta
+    ADD COLUMN name TEXT;
+
+COMMENT ON COLUMN record_meta.name IS 'The kind of metadata (title, description), NULL if unknown.';
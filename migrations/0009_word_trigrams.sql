@@ -0,0 +1,7 @@
+----------------------------
+-- Indexes: WORD_INDEX
+----------------------------
+-- The spelling suggestions are the indexed words sharing trigrams with the unknown query words.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX word_index_word_trgm ON word_index USING GIN (word gin_trgm_ops);
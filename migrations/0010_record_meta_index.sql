@@ -0,0 +1,5 @@
+--------------------------
+-- Indexes: RECORD_META
+--------------------------
+-- The titles of the records are looked up for the title boost.
+CREATE INDEX record_meta_record ON record_meta (record, name);
@@ -0,0 +1,20 @@
+--------------------------
+-- Indexes: RECORD_INDEX
+--------------------------
+-- site: matches the host of the records, or one of its subdomains. The host is stored reversed
+-- in the index, so the subdomains of a host are a range of it.
+ALTER TABLE record_index
+    ADD COLUMN host TEXT GENERATED ALWAYS AS (LOWER(SUBSTRING(uri FROM '^[^:]+://([^/:?#]+)'))) STORED;
+
+COMMENT ON COLUMN record_index.host IS 'The lowercase host of the record URI.';
+
+CREATE INDEX record_index_host ON record_index (REVERSE(host) COLLATE "C");
+
+-- inurl: searches a substring of the URI.
+CREATE INDEX record_index_uri_trgm ON record_index USING GIN (LOWER(uri) gin_trgm_ops);
+
+--------------------------
+-- Indexes: RECORD_META
+--------------------------
+-- meta: searches a substring of the metadata.
+CREATE INDEX record_meta_meta_trgm ON record_meta USING GIN (LOWER(meta) gin_trgm_ops);
@@ -0,0 +1,7 @@
+-------------------------------
+-- Indexes: WORD_RECORD_INDEX
+-------------------------------
+-- The query expressions check whether the records contain their terms.
+DROP INDEX word_record_index_record;
+
+CREATE INDEX word_record_index_record_word ON word_record_index (record, word);
@@ -0,0 +1,15 @@
+----------------------------
+-- Table: WORD_RECORD_INDEX
+----------------------------
+ALTER TABLE word_record_index
+    ADD COLUMN surface TEXT;
+
+COMMENT ON COLUMN word_record_index.surface IS 'The most frequent spelling of the word in the record, lowercased (NULL for records indexed before the spellings were stored).';
+
+----------------------------
+-- Table: WORD_INDEX
+----------------------------
+ALTER TABLE word_index
+    ADD COLUMN surface TEXT;
+
+COMMENT ON COLUMN word_index.surface IS 'The most frequent spelling of the word in the records, shown by the completions instead of the analyzed word.';
@@ -0,0 +1,17 @@
+-------------------------------
+-- Table: RECORD_FIELD_INDEX
+-------------------------------
+CREATE TABLE record_field_index
+(
+    word   TEXT NOT NULL,
+    record INT  NOT NULL REFERENCES record_index (id),
+    field  TEXT NOT NULL,
+    PRIMARY KEY (word, record, field)
+);
+
+CREATE INDEX record_field_index_record ON record_field_index (record);
+
+COMMENT ON TABLE record_field_index IS 'The analyzed words of the title and URL of the records (used for the field boosts).';
+COMMENT ON COLUMN record_field_index.word IS 'The analyzed word.';
+COMMENT ON COLUMN record_field_index.record IS 'Foreign key to the linked record.';
+COMMENT ON COLUMN record_field_index.field IS 'The field containing the word (title, url).';
@@ -0,0 +1,70 @@
+-----------------------
+-- Table: RECORD_INDEX
+-----------------------
+ALTER TABLE record_index
+    ADD COLUMN counted BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- The statistics were recomputed over all the analyzed records.
+UPDATE record_index
+SET counted = analyzed;
+
+COMMENT ON COLUMN record_index.counted IS 'The record is counted in the statistics of the index, and searched.';
+
+----------------------
+-- Table: WORD_INDEX
+----------------------
+ALTER TABLE word_index
+    ADD COLUMN records INT NOT NULL DEFAULT 0;
+
+UPDATE word_index wi
+SET records = s.records
+FROM (SELECT wri.word, COUNT(*) AS records
+      FROM word_record_index wri
+               JOIN record_index ri
+                    ON wri.record = ri.id
+      WHERE ri.counted
+      GROUP BY wri.word) s
+WHERE wi.word = s.word;
+
+COMMENT ON COLUMN word_index.records IS 'The number of counted records containing the word.';
+
+----------------------------
+-- Table: WORD_CORPUS_INDEX
+----------------------------
+-- The rows are updated in place by the statistics, instead of being rebuilt.
+ALTER TABLE word_corpus_index
+    ADD PRIMARY KEY (word, corpus);
+
+DROP INDEX word_corpus_index_word;
+
+CREATE INDEX word_corpus_index_corpus ON word_corpus_index (corpus);
+
+--------------------------------
+-- Table: WORD_STATISTICS_DELTA
+--------------------------------
+CREATE TABLE word_statistics_delta
+(
+    word        TEXT NOT NULL,
+    corpus      INT  NOT NULL,
+    appearances INT  NOT NULL,
+    records     INT  NOT NULL
+);
+
+COMMENT ON TABLE word_statistics_delta IS 'The changes of the word statistics, filled and applied in the transactions updating the statistics (always empty outside of them).';
+COMMENT ON COLUMN word_statistics_delta.word IS 'The word.';
+COMMENT ON COLUMN word_statistics_delta.corpus IS 'The corpus of the added or removed records.';
+COMMENT ON COLUMN word_statistics_delta.appearances IS 'The appearances of the word added to the corpus (negative if removed).';
+COMMENT ON COLUMN word_statistics_delta.records IS 'The records containing the word added to the corpus (negative if removed).';
+
+----------------------------------
+-- Table: CORPUS_STATISTICS_DELTA
+----------------------------------
+CREATE TABLE corpus_statistics_delta
+(
+    corpus  INT NOT NULL,
+    records INT NOT NULL
+);
+
+COMMENT ON TABLE corpus_statistics_delta IS 'The changes of the record counts, filled and applied in the transactions updating the statistics (always empty outside of them).';
+COMMENT ON COLUMN corpus_statistics_delta.corpus IS 'The corpus of the added or removed records.';
+COMMENT ON COLUMN corpus_statistics_delta.records IS 'The records added to the corpus (negative if removed).';
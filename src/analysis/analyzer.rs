@@ -0,0 +1,202 @@
+use rust_stemmers::{Algorithm, Stemmer};
+use std::fmt;
+use std::str::FromStr;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Tokens longer than this are dropped (encoded blobs, minified scripts etc.).
+const MAX_TOKEN_LENGTH: usize = 64;
+
+/// Turns a text into the terms stored in (and looked up from) the index.
+///
+/// The same analyzer must be used for the records of a corpus and for the queries on it, so the
+/// analyzer of each corpus is recorded in `corpus_info.analyzer`.
+pub trait Analyzer: Send + Sync {
+    /// Get the tokens of a text, in order.
+    ///
+    /// # Arguments
+    ///
+    /// * text      - The text to analyze.
+    fn tokens(&self, text: &str) -> Vec<Token>;
+
+    /// Get the terms of a text, in order.
+    ///
+    /// # Arguments
+    ///
+    /// * text      - The text to analyze.
+    fn analyze(&self, text: &str) -> Vec<String> {
+        self.tokens(text)
+            .into_iter()
+            .map(|token| token.term)
+            .collect()
+    }
+}
+
+/// A term of a text, with the byte range of the word it was found in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    /// The analyzed term.
+    pub term: String,
+    /// The start of the word in the text.
+    pub start: usize,
+    /// The end of the word in the text.
+    pub end: usize,
+}
+
+/// The available analyzers.
+#[derive(
+    serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalyzerKind {
+    /// Split on non-alphanumeric characters and lowercase (the corpora indexed before the
+    /// analyzers were introduced).
+    Simple,
+    /// Unicode word segmentation, case folding and accent stripping.
+    Standard,
+    /// `Standard`, with the English Snowball stemmer.
+    #[default]
+    English,
+    /// `Standard`, with the French Snowball stemmer.
+    French,
+    /// `Standard`, with the German Snowball stemmer.
+    German,
+    /// `Standard`, with the Spanish Snowball stemmer.
+    Spanish,
+    /// `Standard`, with the Italian Snowball stemmer.
+    Italian,
+    /// `Standard`, with the Portuguese Snowball stemmer.
+    Portuguese,
+}
+
+/// Implementation for `AnalyzerKind` structure.
+impl AnalyzerKind {
+    /// All the analyzers.
+    pub const ALL: [AnalyzerKind; 8] = [
+        AnalyzerKind::Simple,
+        AnalyzerKind::Standard,
+        AnalyzerKind::English,
+        AnalyzerKind::French,
+        AnalyzerKind::German,
+        AnalyzerKind::Spanish,
+        AnalyzerKind::Italian,
+        AnalyzerKind::Portuguese,
+    ];
+
+    /// The name of the analyzer, as stored in `corpus_info.analyzer`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnalyzerKind::Simple => "simple",
+            AnalyzerKind::Standard => "standard",
+            AnalyzerKind::English => "english",
+            AnalyzerKind::French => "french",
+            AnalyzerKind::German => "german",
+            AnalyzerKind::Spanish => "spanish",
+            AnalyzerKind::Italian => "italian",
+            AnalyzerKind::Portuguese => "portuguese",
+        }
+    }
+
+    /// The stemming algorithm of the analyzer, if any.
+    fn algorithm(&self) -> Option<Algorithm> {
+        match self {
+            AnalyzerKind::Simple | AnalyzerKind::Standard => None,
+            AnalyzerKind::English => Some(Algorithm::English),
+            AnalyzerKind::French => Some(Algorithm::French),
+            AnalyzerKind::German => Some(Algorithm::German),
+            AnalyzerKind::Spanish => Some(Algorithm::Spanish),
+            AnalyzerKind::Italian => Some(Algorithm::Italian),
+            AnalyzerKind::Portuguese => Some(Algorithm::Portuguese),
+        }
+    }
+}
+
+impl fmt::Display for AnalyzerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for AnalyzerKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        AnalyzerKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+            .ok_or_else(|| format!("Unknown analyzer {}", name))
+    }
+}
+
+/// Get the analyzer of the given kind.
+///
+/// # Arguments
+///
+/// * kind      - The analyzer to create.
+pub fn get_analyzer(kind: AnalyzerKind) -> Box<dyn Analyzer> {
+    match kind {
+        AnalyzerKind::Simple => Box::new(SimpleAnalyzer),
+        kind => Box::new(StandardAnalyzer {
+            stemmer: kind.algorithm().map(Stemmer::create),
+        }),
+    }
+}
+
+/// Splits on non-alphanumeric characters and lowercases.
+pub struct SimpleAnalyzer;
+
+impl Analyzer for SimpleAnalyzer {
+    fn tokens(&self, text: &str) -> Vec<Token> {
+        let mut tokens = vec![];
+        let mut start = None;
+        for (offset, c) in text.char_indices().chain([(text.len(), ' ')]) {
+            match (start, c.is_alphanumeric()) {
+                (None, true) => start = Some(offset),
+                (Some(begin), false) => {
+                    let word = &text[begin..offset];
+                    if word.chars().count() <= MAX_TOKEN_LENGTH {
+                        tokens.push(Token {
+                            term: word.to_lowercase(),
+                            start: begin,
+                            end: offset,
+                        });
+                    }
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        tokens
+    }
+}
+
+/// Splits on the Unicode word boundaries (UAX #29), folds the case, stems the words and strips
+/// their accents.
+///
+/// Words keep their inner punctuation, e.g. `foo_bar`, `3.14` or `don't`.
+pub struct StandardAnalyzer {
+    /// The stemmer, if any.
+    stemmer: Option<Stemmer>,
+}
+
+impl Analyzer for StandardAnalyzer {
+    fn tokens(&self, text: &str) -> Vec<Token> {
+        text.unicode_word_indices()
+            .filter(|(_, word)| word.chars().count() <= MAX_TOKEN_LENGTH)
+            .map(|(start, word)| {
+                let term = word.to_lowercase();
+                // The stemmers expect the accented forms.
+                let term = match &self.stemmer {
+                    Some(stemmer) => stemmer.stem(&term).into_owned(),
+                    None => term,
+                };
+                Token {
+                    term: term.nfd().filter(|c| !is_combining_mark(*c)).collect(),
+                    start,
+                    end: start + word.len(),
+                }
+            })
+            .collect()
+    }
+}
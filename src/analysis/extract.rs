@@ -0,0 +1,350 @@
+use crate::warc::WarcRecord;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use log::warn;
+
+/// The elements whose content is not part of the main text.
+const BOILERPLATE: [&str; 10] = [
+    "script", "style", "noscript", "template", "svg", "iframe", "nav", "header", "footer", "aside",
+];
+
+/// The elements that do not separate words.
+const INLINE: [&str; 14] = [
+    "a", "abbr", "b", "bdi", "code", "em", "i", "mark", "small", "span", "strong", "sub", "sup",
+    "u",
+];
+
+/// The text extracted from a record.
+#[derive(Debug, Default, PartialEq)]
+pub struct Document {
+    /// The `<title>` of an HTML page.
+    pub title: Option<String>,
+    /// The meta description of an HTML page.
+    pub description: Option<String>,
+    /// The main text.
+    pub text: String,
+}
+
+/// Extract the text of a record.
+///
+/// The HTTP response of `response` records is parsed, its body de-chunked and decompressed, and
+/// decoded with the declared (or sniffed) charset. Bodies with an unsupported encoding, or larger
+/// than `max_body_size` once decompressed, are dropped. HTML pages are stripped of their markup and
+/// boilerplate (scripts, styles, navigation, headers and footers); other textual bodies are kept as
+/// is, and binary ones are dropped. Blocks that are not HTTP responses are read as UTF-8.
+///
+/// # Arguments
+///
+/// * record        - The WARC record.
+/// * max_body_size - The maximum size of a decompressed body.
+pub fn extract_document(record: &WarcRecord, max_body_size: usize) -> Document {
+    let payload = match record.http_payload() {
+        Some(payload) => payload,
+        None => {
+            return Document {
+                text: String::from_utf8_lossy(&record.block).into_owned(),
+                ..Default::default()
+            }
+        }
+    };
+
+    let body = match payload.decoded_body(max_body_size) {
+        Ok(body) => body,
+        Err(e) => {
+            let id = record.trec_id().or_else(|| record.record_id());
+            warn!("Skipped the body of {}: {}", id.unwrap_or_default(), e);
+            return Document::default();
+        }
+    };
+
+    let content_type = payload.content_type().unwrap_or_default().to_lowercase();
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    let charset = content_type
+        .split(';')
+        .filter_map(|param| param.trim().strip_prefix("charset="))
+        .next()
+        .map(|charset| charset.trim_matches(['"', '\'']));
+    let html = mime.contains("html") || (mime.is_empty() && sniff_html(&body));
+
+    if !html && !mime.is_empty() && !mime.starts_with("text/") {
+        return Document::default();
+    }
+    let charset = charset.or_else(|| match html {
+        true => sniff_charset(&body),
+        false => None,
+    });
+    let text = decode(&body, charset);
+    match html {
+        true => extract_html(&text),
+        false => Document {
+            text,
+            ..Default::default()
+        },
+    }
+}
+
+/// Decode a body with the given charset.
+///
+/// Without a known charset, the body is read as UTF-8, or as Windows-1252 if it is not valid
+/// UTF-8.
+///
+/// # Arguments
+///
+/// * body      - The body.
+/// * charset   - The declared charset.
+fn decode(body: &[u8], charset: Option<&str>) -> String {
+    let encoding = charset
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .unwrap_or(match std::str::from_utf8(body) {
+            Ok(_) => UTF_8,
+            Err(_) => WINDOWS_1252,
+        });
+    encoding.decode(body).0.into_owned()
+}
+
+/// Check if a body without content type looks like HTML.
+fn sniff_html(body: &[u8]) -> bool {
+    let start = String::from_utf8_lossy(&body[..body.len().min(256)]).to_lowercase();
+    let start = start.trim_start();
+    start.starts_with("<!doctype html") || start.starts_with("<html")
+}
+
+/// Find the charset declared by a `<meta>` element at the start of an HTML page.
+fn sniff_charset(body: &[u8]) -> Option<&'static str> {
+    let start = String::from_utf8_lossy(&body[..body.len().min(1024)]).to_lowercase();
+    let position = start.find("charset=")? + "charset=".len();
+    let label: String = start[position..]
+        .trim_start_matches(['"', '\''])
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+        .collect();
+    Encoding::for_label(label.as_bytes()).map(|encoding| encoding.name())
+}
+
+/// Extract the title, the meta description and the main text of an HTML page.
+///
+/// # Arguments
+///
+/// * html      - The decoded page.
+pub fn extract_html(html: &str) -> Document {
+    let mut document = Document::default();
+    let mut text = String::new();
+    // The boilerplate elements the parser is in.
+    let mut boilerplate = 0;
+    let mut rest = html;
+
+    while let Some(open) = rest.find('<') {
+        if boilerplate == 0 {
+            text.push_str(&decode_entities(&rest[..open]));
+        }
+        rest = &rest[open..];
+
+        // Comments, doctype and processing instructions.
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+
+        let end = match tag_end(rest) {
+            Some(end) => end,
+            None => {
+                // A lone `<`.
+                if boilerplate == 0 {
+                    text.push('<');
+                }
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if name.is_empty() {
+            continue;
+        }
+
+        match (name.as_str(), closing) {
+            // The raw text elements end at their closing tag.
+            ("script" | "style" | "title", false) if !tag.ends_with('/') => {
+                let close = format!("</{}", name);
+                let end = find_ignore_case(rest, &close).unwrap_or(rest.len());
+                if name == "title" && document.title.is_none() {
+                    document.title = non_empty(&decode_entities(&rest[..end]));
+                }
+                rest = &rest[end..];
+                rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            }
+            ("meta", false) => {
+                let attributes = attributes(tag);
+                let name = attribute(&attributes, "name")
+                    .or_else(|| attribute(&attributes, "property"))
+                    .map(str::to_ascii_lowercase);
+                if matches!(name.as_deref(), Some("description" | "og:description"))
+                    && document.description.is_none()
+                {
+                    document.description = attribute(&attributes, "content")
+                        .and_then(|c| non_empty(&decode_entities(c)));
+                }
+            }
+            (name, _) if BOILERPLATE.contains(&name) => {
+                let self_closing = tag.ends_with('/');
+                match closing {
+                    true => boilerplate = usize::saturating_sub(boilerplate, 1),
+                    false if !self_closing => boilerplate += 1,
+                    false => {}
+                }
+            }
+            (name, _) if INLINE.contains(&name) => {}
+            // Block elements separate the words.
+            _ => text.push(' '),
+        }
+    }
+    if boilerplate == 0 {
+        text.push_str(&decode_entities(rest));
+    }
+
+    document.text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    document
+}
+
+/// Find the `>` ending a tag, skipping the quoted attribute values.
+///
+/// # Returns
+///
+/// * `None` if the `<` does not start a tag.
+fn tag_end(tag: &str) -> Option<usize> {
+    let first = tag[1..].chars().next()?;
+    if !(first.is_ascii_alphabetic() || first == '/') {
+        return None;
+    }
+    let mut quote = None;
+    for (index, c) in tag.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if q == c => quote = None,
+            (None, '>') => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parse the attributes of a tag.
+///
+/// # Arguments
+///
+/// * tag       - The content of the tag, without the `<>`.
+fn attributes(tag: &str) -> Vec<(String, String)> {
+    let mut attributes = vec![];
+    let mut chars = tag
+        .trim_end_matches('/')
+        .trim_start_matches(|c: char| c.is_ascii_alphanumeric())
+        .chars()
+        .peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let name: String =
+            std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace() && *c != '=')).collect();
+        if name.is_empty() {
+            break;
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut value = String::new();
+        if chars.next_if_eq(&'=').is_some() {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            match chars.next_if(|c| *c == '"' || *c == '\'') {
+                Some(quote) => value.extend(chars.by_ref().take_while(|c| *c != quote)),
+                None => value.extend(std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace()))),
+            }
+        }
+        attributes.push((name.to_ascii_lowercase(), value));
+    }
+    attributes
+}
+
+/// Get the value of an attribute.
+fn attribute<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Find a string, ignoring the ASCII case.
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+/// Collapse the whitespace of a text, if it is not empty.
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.is_empty() {
+        true => None,
+        false => Some(text),
+    }
+}
+
+/// Decode the character references of a text.
+///
+/// # Arguments
+///
+/// * text      - The text, between two tags.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let character = reference.and_then(|name| match name.strip_prefix('#') {
+            Some(code) => match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => code.parse().ok(),
+            }
+            .and_then(char::from_u32),
+            None => match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "copy" => Some('©'),
+                "reg" => Some('®'),
+                "hellip" => Some('…'),
+                "mdash" => Some('—'),
+                "ndash" => Some('–'),
+                _ => None,
+            },
+        });
+        match (reference, character) {
+            (Some(reference), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[reference.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
@@ -0,0 +1,12 @@
+/// Text analysis.
+///
+/// Extracts the text of the records, and turns it (and the queries) into index terms: word
+/// segmentation, case folding, accent stripping and stemming. Also expands the query terms with
+/// their synonyms.
+mod analyzer;
+mod extract;
+mod synonyms;
+
+pub use analyzer::*;
+pub use extract::*;
+pub use synonyms::*;
@@ -0,0 +1,95 @@
+use crate::analysis::Analyzer;
+use log::warn;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A synonym dictionary.
+///
+/// The dictionaries are text files in the Solr format, one rule per line:
+///
+/// ```text
+/// # Equivalent words, each one expands to the others.
+/// car, automobile, auto
+/// # Explicit mappings, the words on the left expand to the ones on the right.
+/// jaguar, puma => cat
+/// ```
+///
+/// The words are analyzed like the queries, and the rules made of several words are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct Synonyms {
+    /// The expansions of each term.
+    terms: HashMap<String, Vec<String>>,
+}
+
+/// Implementation for `Synonyms` structure.
+impl Synonyms {
+    /// Load a synonym dictionary.
+    ///
+    /// # Arguments
+    ///
+    /// * path      - The dictionary file.
+    /// * analyzer  - The analyzer of the words.
+    pub fn load(path: &Path, analyzer: &dyn Analyzer) -> std::io::Result<Synonyms> {
+        Ok(Synonyms::parse(&std::fs::read_to_string(path)?, analyzer))
+    }
+
+    /// Parse a synonym dictionary.
+    ///
+    /// # Arguments
+    ///
+    /// * text      - The content of the dictionary.
+    /// * analyzer  - The analyzer of the words.
+    pub fn parse(text: &str, analyzer: &dyn Analyzer) -> Synonyms {
+        let mut synonyms = Synonyms::default();
+        let words = |list: &str| -> Vec<String> {
+            list.split(',')
+                .filter(|word| !word.trim().is_empty())
+                .filter_map(|word| match analyzer.analyze(word).as_slice() {
+                    [term] => Some(term.clone()),
+                    _ => {
+                        warn!("Ignored synonym: {}", word.trim());
+                        None
+                    }
+                })
+                .collect()
+        };
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            match line.split_once("=>") {
+                Some((from, to)) => {
+                    let to = words(to);
+                    for term in words(from) {
+                        synonyms.add(&term, &to);
+                    }
+                }
+                None => {
+                    let terms = words(line);
+                    for term in terms.iter() {
+                        synonyms.add(term, &terms);
+                    }
+                }
+            }
+        }
+        synonyms
+    }
+
+    /// Add expansions of a term, skipping the term itself and the known ones.
+    fn add(&mut self, term: &str, expansions: &[String]) {
+        let known = self.terms.entry(term.to_string()).or_default();
+        for expansion in expansions {
+            if expansion != term && !known.contains(expansion) {
+                known.push(expansion.clone());
+            }
+        }
+    }
+
+    /// Get the expansions of a term.
+    ///
+    /// # Arguments
+    ///
+    /// * term      - The analyzed term.
+    pub fn get(&self, term: &str) -> &[String] {
+        self.terms.get(term).map(Vec::as_slice).unwrap_or_default()
+    }
+}
@@ -1,8 +1,16 @@
+use crate::analysis::{get_analyzer, AnalyzerKind, Synonyms};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
 /// Structure that contains the Settings information.
 #[derive(serde::Deserialize)]
 pub struct Settings {
     pub database: DatabaseSettings,
     pub application_port: u16,
+    #[serde(default)]
+    pub search: SearchSettings,
+    #[serde(default)]
+    pub analysis: AnalysisSettings,
 }
 
 /// Structure that contains the database information.
@@ -13,6 +21,403 @@ pub struct DatabaseSettings {
     pub port: u16,
     pub host: String,
     pub database_name: String,
+    /// Apply the pending migrations on startup, instead of refusing to start.
+    #[serde(default)]
+    pub auto_migrate: bool,
+}
+
+/// Structure that contains the search information.
+#[derive(serde::Deserialize, Clone)]
+pub struct SearchSettings {
+    /// The ranking model used when the query does not select one.
+    #[serde(default)]
+    pub ranking: RankingModel,
+    /// The BM25 parameters.
+    #[serde(default)]
+    pub bm25: Bm25Settings,
+    /// The page size used when the query does not set a limit.
+    #[serde(default = "default_limit")]
+    pub default_limit: usize,
+    /// The maximum page size.
+    #[serde(default = "max_limit")]
+    pub max_limit: usize,
+    /// The proximity boost: `proximity_boost / d` is added to the score of the records where two
+    /// query words appear `d` words apart (0 disables it).
+    #[serde(default)]
+    pub proximity_boost: f64,
+    /// The stopword policy.
+    #[serde(default)]
+    pub stopwords: StopwordSettings,
+    /// The snippets of the results.
+    #[serde(default)]
+    pub snippets: SnippetSettings,
+    /// The spelling suggestions of the unknown query words.
+    #[serde(default)]
+    pub spelling: SpellingSettings,
+    /// The prefix completions of `/suggest`.
+    #[serde(default)]
+    pub completion: CompletionSettings,
+    /// The synonyms the query terms are expanded with.
+    #[serde(default)]
+    pub synonyms: SynonymSettings,
+    /// The pseudo-relevance feedback.
+    #[serde(default)]
+    pub feedback: FeedbackSettings,
+    /// The boosts of the query words found in the title or URL of a record, unless the request
+    /// sets `boosts`.
+    #[serde(default)]
+    pub boosts: FieldBoosts,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        SearchSettings {
+            ranking: RankingModel::default(),
+            bm25: Bm25Settings::default(),
+            default_limit: default_limit(),
+            max_limit: max_limit(),
+            proximity_boost: 0_f64,
+            stopwords: StopwordSettings::default(),
+            snippets: SnippetSettings::default(),
+            spelling: SpellingSettings::default(),
+            completion: CompletionSettings::default(),
+            synonyms: SynonymSettings::default(),
+            feedback: FeedbackSettings::default(),
+            boosts: FieldBoosts::default(),
+        }
+    }
+}
+
+fn default_limit() -> usize {
+    10
+}
+
+fn max_limit() -> usize {
+    100
+}
+
+/// The available ranking models.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingModel {
+    /// Cosine similarity over the stored TF-IDF, mixed with the corpus score.
+    #[default]
+    Cosine,
+    /// Cosine similarity over the stored TF-IDF of the records only.
+    RecordCosine,
+    /// Okapi BM25 over the record postings.
+    Bm25,
+}
+
+/// Structure that contains the stopword policy.
+///
+/// The stopwords are removed from the query, unless the query is made of stopwords only.
+#[derive(serde::Deserialize, Clone)]
+pub struct StopwordSettings {
+    /// The words appearing in a larger fraction of the records are stopwords (1 disables it).
+    #[serde(default = "stopword_threshold")]
+    pub threshold: f64,
+    /// Where the stopwords are removed from the query.
+    #[serde(default)]
+    pub mode: StopwordMode,
+    /// The stopwords of the corpora of each analyzer, e.g. `english: ["a", "the"]`. The words
+    /// are analyzed like the query.
+    #[serde(default)]
+    pub lists: HashMap<String, Vec<String>>,
+}
+
+impl Default for StopwordSettings {
+    fn default() -> Self {
+        StopwordSettings {
+            threshold: stopword_threshold(),
+            mode: StopwordMode::default(),
+            lists: HashMap::new(),
+        }
+    }
+}
+
+fn stopword_threshold() -> f64 {
+    0.9
+}
+
+/// Where the stopwords are removed from the query.
+#[derive(serde::Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StopwordMode {
+    /// Kept in phrases and `NEAR/n` clauses, where they are matched but not scored.
+    #[default]
+    KeepInPhrases,
+    /// Removed everywhere: the other words of a phrase must appear within its length.
+    Remove,
+}
+
+/// Structure that contains the snippet settings.
+#[derive(serde::Deserialize, Clone)]
+pub struct SnippetSettings {
+    /// The number of words of a snippet (0 disables the snippets).
+    #[serde(default = "snippet_length")]
+    pub length: usize,
+    /// The marker inserted before the matched words.
+    #[serde(default = "pre_tag")]
+    pub pre_tag: String,
+    /// The marker inserted after the matched words.
+    #[serde(default = "post_tag")]
+    pub post_tag: String,
+}
+
+impl Default for SnippetSettings {
+    fn default() -> Self {
+        SnippetSettings {
+            length: snippet_length(),
+            pre_tag: pre_tag(),
+            post_tag: post_tag(),
+        }
+    }
+}
+
+fn snippet_length() -> usize {
+    30
+}
+
+fn pre_tag() -> String {
+    String::from("<b>")
+}
+
+fn post_tag() -> String {
+    String::from("</b>")
+}
+
+/// Structure that contains the spelling suggestion settings.
+///
+/// The query words missing from the index are matched against the indexed words by trigram
+/// similarity, and the closest ones are suggested, favouring the most frequent.
+#[derive(serde::Deserialize, Clone)]
+pub struct SpellingSettings {
+    /// The maximum number of suggestions of each unknown word (0 disables them).
+    #[serde(default = "suggestions")]
+    pub suggestions: usize,
+    /// The minimum trigram similarity of a suggestion, between 0 and 1. The `pg_trgm` index only
+    /// finds the words over its `pg_trgm.similarity_threshold` (0.3 by default).
+    #[serde(default = "min_similarity")]
+    pub min_similarity: f64,
+    /// Also search the suggestions of the unknown words, unless the request sets `expand`.
+    #[serde(default)]
+    pub expand: bool,
+    /// The query weight of the suggestions searched in place of an unknown word.
+    #[serde(default = "expansion_weight")]
+    pub expansion_weight: f64,
+}
+
+impl Default for SpellingSettings {
+    fn default() -> Self {
+        SpellingSettings {
+            suggestions: suggestions(),
+            min_similarity: min_similarity(),
+            expand: false,
+            expansion_weight: expansion_weight(),
+        }
+    }
+}
+
+fn suggestions() -> usize {
+    3
+}
+
+fn min_similarity() -> f64 {
+    0.3
+}
+
+fn expansion_weight() -> f64 {
+    0.5
+}
+
+/// Structure that contains the completion settings.
+#[derive(serde::Deserialize, Clone, Copy)]
+pub struct CompletionSettings {
+    /// The number of completions returned when the request does not set a limit.
+    #[serde(default = "completion_default_limit")]
+    pub default_limit: usize,
+    /// The maximum number of completions, kept in memory for each prefix.
+    #[serde(default = "completion_max_limit")]
+    pub max_limit: usize,
+}
+
+impl Default for CompletionSettings {
+    fn default() -> Self {
+        CompletionSettings {
+            default_limit: completion_default_limit(),
+            max_limit: completion_max_limit(),
+        }
+    }
+}
+
+fn completion_default_limit() -> usize {
+    5
+}
+
+fn completion_max_limit() -> usize {
+    20
+}
+
+/// Structure that contains the synonym settings.
+///
+/// The query terms are expanded with their synonyms, which only count with a lower weight.
+#[derive(serde::Deserialize, Clone)]
+pub struct SynonymSettings {
+    /// The synonym dictionary of the corpora of each analyzer, e.g.
+    /// `english: "synonyms/english.txt"`.
+    #[serde(default)]
+    pub files: HashMap<String, PathBuf>,
+    /// The query weight of the synonyms.
+    #[serde(default = "synonym_weight")]
+    pub weight: f64,
+    /// The loaded dictionaries, by analyzer name.
+    #[serde(skip)]
+    pub dictionaries: HashMap<String, Synonyms>,
+}
+
+impl Default for SynonymSettings {
+    fn default() -> Self {
+        SynonymSettings {
+            files: HashMap::new(),
+            weight: synonym_weight(),
+            dictionaries: HashMap::new(),
+        }
+    }
+}
+
+fn synonym_weight() -> f64 {
+    0.5
+}
+
+/// Implementation for `SynonymSettings` structure.
+impl SynonymSettings {
+    /// Load the synonym dictionaries, analyzing their words with the analyzer they are set for.
+    ///
+    /// # Returns
+    ///
+    /// * `std::io::Error` if a file can not be read, or its analyzer is unknown.
+    pub fn load(&mut self) -> std::io::Result<()> {
+        for (name, path) in self.files.iter() {
+            let kind = name
+                .parse::<AnalyzerKind>()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+            let synonyms = Synonyms::load(path, get_analyzer(kind).as_ref())
+                .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+            self.dictionaries.insert(kind.name().to_string(), synonyms);
+        }
+        Ok(())
+    }
+}
+
+/// Structure that contains the pseudo-relevance feedback settings.
+///
+/// The query is run once, and the terms of its best results are added to it (Rocchio), with a
+/// lower weight, before it is run again.
+#[derive(serde::Deserialize, Clone, Copy)]
+pub struct FeedbackSettings {
+    /// Expand the queries, unless the request sets `feedback`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The number of best results assumed to be relevant.
+    #[serde(default = "feedback_documents")]
+    pub documents: usize,
+    /// The number of terms added to the query.
+    #[serde(default = "feedback_terms")]
+    pub terms: usize,
+    /// The query weight of the best added term, the others are weighted in proportion (the
+    /// Rocchio `beta`).
+    #[serde(default = "feedback_weight")]
+    pub weight: f64,
+}
+
+impl Default for FeedbackSettings {
+    fn default() -> Self {
+        FeedbackSettings {
+            enabled: false,
+            documents: feedback_documents(),
+            terms: feedback_terms(),
+            weight: feedback_weight(),
+        }
+    }
+}
+
+fn feedback_documents() -> usize {
+    10
+}
+
+fn feedback_terms() -> usize {
+    5
+}
+
+fn feedback_weight() -> f64 {
+    0.5
+}
+
+/// Structure that contains the boosts of the record fields.
+///
+/// The score of a query word in a record is multiplied by the boost of each field containing it
+/// (1 disables it).
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct FieldBoosts {
+    /// The boost of the words found in the title of the record.
+    #[serde(default = "no_boost")]
+    pub title: f64,
+    /// The boost of the words found in the URL of the record.
+    #[serde(default = "no_boost")]
+    pub url: f64,
+}
+
+impl Default for FieldBoosts {
+    fn default() -> Self {
+        FieldBoosts {
+            title: no_boost(),
+            url: no_boost(),
+        }
+    }
+}
+
+fn no_boost() -> f64 {
+    1_f64
+}
+
+/// Structure that contains the BM25 parameters.
+#[derive(serde::Deserialize, Clone, Copy)]
+pub struct Bm25Settings {
+    /// Term frequency saturation.
+    pub k1: f64,
+    /// Document length normalization (0 disables it, 1 is full normalization).
+    pub b: f64,
+}
+
+impl Default for Bm25Settings {
+    fn default() -> Self {
+        Bm25Settings { k1: 1.2, b: 0.75 }
+    }
+}
+
+/// Structure that contains the text analysis information.
+#[derive(serde::Deserialize, Clone, Copy)]
+pub struct AnalysisSettings {
+    /// The analyzer of the new corpora. Existing corpora keep the analyzer they were created with.
+    #[serde(default)]
+    pub analyzer: AnalyzerKind,
+    /// The maximum size of a body once decompressed, in bytes. Larger bodies are not indexed.
+    #[serde(default = "max_body_size")]
+    pub max_body_size: usize,
+}
+
+impl Default for AnalysisSettings {
+    fn default() -> Self {
+        AnalysisSettings {
+            analyzer: AnalyzerKind::default(),
+            max_body_size: max_body_size(),
+        }
+    }
+}
+
+fn max_body_size() -> usize {
+    10 * 1024 * 1024
 }
 
 /// Database settings initializer.
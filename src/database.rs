@@ -0,0 +1,162 @@
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::{Acquire, PgPool};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The schema migrations, embedded from the `migrations` directory.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Errors raised when the database schema does not match the application.
+#[derive(Debug)]
+pub enum SchemaError {
+    /// The database returned an error.
+    Database(sqlx::Error),
+    /// No migration has been applied to the database.
+    Uninitialized,
+    /// The database is missing the listed migrations.
+    Outdated(Vec<i64>),
+    /// The database was migrated by an incompatible version of the application.
+    Incompatible(String),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Database(e) => write!(f, "Database error: {}", e),
+            SchemaError::Uninitialized => {
+                write!(f, "The database is not initialized, run `warcse migrate`")
+            }
+            SchemaError::Outdated(versions) => write!(
+                f,
+                "The database is missing migrations {:?}, run `warcse migrate`",
+                versions
+            ),
+            SchemaError::Incompatible(reason) => {
+                write!(f, "Incompatible database schema: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl From<sqlx::Error> for SchemaError {
+    fn from(e: sqlx::Error) -> Self {
+        SchemaError::Database(e)
+    }
+}
+
+/// Apply the pending migrations to the database.
+///
+/// A database created by the former `docker/init.sql` is baselined first.
+///
+/// # Arguments
+///
+/// * pool  - `PgPool` the PostgreSQL pool.
+pub async fn migrate(pool: &PgPool) -> Result<(), MigrateError> {
+    baseline(pool).await?;
+    MIGRATOR.run(pool).await
+}
+
+/// Record the initial schema as applied to a database created by the former `docker/init.sql`.
+///
+/// Such a database has the tables of the initial schema, but no migration history. The first
+/// migration is recorded as applied, so that the next ones upgrade it. Other databases are left
+/// untouched.
+///
+/// # Arguments
+///
+/// * pool  - `PgPool` the PostgreSQL pool.
+async fn baseline(pool: &PgPool) -> Result<(), MigrateError> {
+    let mut conn = pool.acquire().await?;
+    let (legacy,): (bool,) = sqlx::query_as(
+        r#"
+        SELECT to_regclass('_sqlx_migrations') IS NULL
+           AND to_regclass('corpus_info') IS NOT NULL
+        "#,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let initial = match MIGRATOR.iter().next() {
+        Some(initial) if legacy => initial,
+        _ => return Ok(()),
+    };
+
+    let mut tx = conn.begin().await?;
+    tx.ensure_migrations_table().await?;
+    sqlx::query(
+        r#"
+        INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+        VALUES ($1, $2, TRUE, $3, 0)
+        "#,
+    )
+    .bind(initial.version)
+    .bind(&*initial.description)
+    .bind(&*initial.checksum)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Check that the database schema matches the migrations embedded in the application.
+///
+/// # Arguments
+///
+/// * pool  - `PgPool` the PostgreSQL pool.
+///
+/// # Returns
+///
+/// * `Ok` if every embedded migration is applied, and nothing else.
+/// * `SchemaError` describing the mismatch otherwise.
+pub async fn check_schema(pool: &PgPool) -> Result<(), SchemaError> {
+    let (initialized,): (bool,) =
+        sqlx::query_as("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(pool)
+            .await?;
+    if !initialized {
+        return Err(SchemaError::Uninitialized);
+    }
+
+    let applied: Vec<(i64, bool, Vec<u8>)> =
+        sqlx::query_as("SELECT version, success, checksum FROM _sqlx_migrations")
+            .fetch_all(pool)
+            .await?;
+    let mut applied: HashMap<i64, (bool, Vec<u8>)> = applied
+        .into_iter()
+        .map(|(version, success, checksum)| (version, (success, checksum)))
+        .collect();
+
+    let mut missing = vec![];
+    for migration in MIGRATOR.iter() {
+        match applied.remove(&migration.version) {
+            None => missing.push(migration.version),
+            Some((false, _)) => {
+                return Err(SchemaError::Incompatible(format!(
+                    "migration {} failed to apply",
+                    migration.version
+                )))
+            }
+            Some((true, checksum)) if checksum != *migration.checksum => {
+                return Err(SchemaError::Incompatible(format!(
+                    "migration {} was modified after it was applied",
+                    migration.version
+                )))
+            }
+            Some(_) => {}
+        }
+    }
+
+    if let Some(version) = applied.keys().max() {
+        return Err(SchemaError::Incompatible(format!(
+            "migration {} is unknown to this version of warcse",
+            version
+        )));
+    }
+    if !missing.is_empty() {
+        return Err(SchemaError::Outdated(missing));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::domain::Completion;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// A node of the completion trie.
+#[derive(Default)]
+struct Node {
+    /// The children of the node, sorted by their byte.
+    children: Vec<(u8, u32)>,
+    /// The best completions of the node's prefix, best first.
+    top: Vec<u32>,
+}
+
+/// The completions of the indexed words.
+///
+/// A byte trie of the case folded and accent stripped words, where each node keeps the most
+/// frequent words starting with its prefix, so that a lookup only walks the prefix.
+#[derive(Default)]
+pub struct Completions {
+    /// The words with their total appearances, most frequent first.
+    words: Vec<(String, i64)>,
+    /// The nodes of the trie, the root first.
+    nodes: Vec<Node>,
+}
+
+/// Implementation for `Completions` structure.
+impl Completions {
+    /// Create a new `Completions` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * words     - The words with their total appearances.
+    /// * depth     - The number of completions kept for each prefix.
+    pub fn new(mut words: Vec<(String, i64)>, depth: usize) -> Completions {
+        words.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        // The words are inserted best first, so the first ones reaching a node are its best.
+        let mut nodes = vec![Node::default()];
+        for (index, (word, _)) in words.iter().enumerate() {
+            let mut node = 0;
+            if nodes[node].top.len() < depth {
+                nodes[node].top.push(index as u32);
+            }
+            for byte in fold(word).bytes() {
+                node = match nodes[node]
+                    .children
+                    .binary_search_by_key(&byte, |(b, _)| *b)
+                {
+                    Ok(child) => nodes[node].children[child].1 as usize,
+                    Err(child) => {
+                        nodes.push(Node::default());
+                        let id = nodes.len() - 1;
+                        nodes[node].children.insert(child, (byte, id as u32));
+                        id
+                    }
+                };
+                if nodes[node].top.len() < depth {
+                    nodes[node].top.push(index as u32);
+                }
+            }
+        }
+
+        Completions { words, nodes }
+    }
+
+    /// The number of words.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Check if there are no words.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Get the most frequent words starting with a prefix.
+    ///
+    /// The prefix is case folded and stripped of its accents, like the words in the trie.
+    ///
+    /// # Arguments
+    ///
+    /// * prefix    - The prefix.
+    /// * limit     - The maximum number of words (at most the `depth` of the trie).
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<Completion> {
+        let prefix = fold(prefix.trim());
+
+        let mut node = match self.nodes.first() {
+            Some(root) => root,
+            None => return vec![],
+        };
+        for byte in prefix.bytes() {
+            node = match node.children.binary_search_by_key(&byte, |(b, _)| *b) {
+                Ok(child) => &self.nodes[node.children[child].1 as usize],
+                Err(_) => return vec![],
+            };
+        }
+
+        node.top
+            .iter()
+            .take(limit)
+            .map(|index| {
+                let (word, appearances) = &self.words[*index as usize];
+                Completion {
+                    word: word.clone(),
+                    appearances: *appearances,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Fold the case of a word and strip its accents.
+///
+/// # Arguments
+///
+/// * word      - The word.
+fn fold(word: &str) -> String {
+    word.to_lowercase()
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .collect()
+}
@@ -1,4 +1,12 @@
-/// This mod contains the structs used for returning results.
+/// This mod contains the structs used for returning results, and the ranking of the results.
+mod completion;
+mod page;
+pub mod query;
+pub mod ranking;
 mod response;
+mod snippet;
 
+pub use completion::*;
+pub use page::*;
 pub use response::*;
+pub use snippet::*;
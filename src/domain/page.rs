@@ -0,0 +1,215 @@
+use crate::domain::{Expansion, Explanation, SearchResult, Suggestion};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// The requested slice of the ranked results.
+#[derive(Debug, Clone)]
+pub struct Page {
+    /// The number of results to skip (ignored when `after` is set).
+    pub offset: usize,
+    /// The maximum number of results to return.
+    pub limit: usize,
+    /// Continue after the last result of a previous page.
+    pub after: Option<Cursor>,
+}
+
+/// The position of a result in the ranking.
+///
+/// Results are ordered by descending score and then by ascending record id, so a cursor stays
+/// valid even if results with equal scores exist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    /// The score of the result.
+    pub score: f64,
+    /// The record id of the result.
+    pub record: i32,
+}
+
+/// Implementation for `Cursor` structure.
+impl Cursor {
+    /// Encode the cursor into an opaque token.
+    pub fn encode(&self) -> String {
+        format!("{:016x}{:08x}", self.score.to_bits(), self.record)
+    }
+
+    /// Decode a token created by `encode`.
+    ///
+    /// # Arguments
+    ///
+    /// * token     - The token.
+    ///
+    /// # Returns
+    ///
+    /// * `None` if the token is not valid.
+    pub fn decode(token: &str) -> Option<Cursor> {
+        if token.len() != 24 || !token.is_ascii() {
+            return None;
+        }
+        Some(Cursor {
+            score: f64::from_bits(u64::from_str_radix(&token[..16], 16).ok()?),
+            record: u32::from_str_radix(&token[16..], 16).ok()? as i32,
+        })
+    }
+}
+
+/// A scored record.
+///
+/// Only holds the ids of the record, the rest of its information is loaded for the hits of the
+/// requested page only.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+    /// The record id.
+    pub record: i32,
+    /// The corpus score.
+    pub corpus_score: f64,
+    /// The record score.
+    pub record_score: f64,
+    /// The combined result of corpus and record.
+    pub score_mixed: f64,
+}
+
+/// A page of ranked hits.
+#[derive(Debug)]
+pub struct RankedPage {
+    /// The total number of matching records.
+    pub total_hits: usize,
+    /// The hits of the page.
+    pub hits: Vec<Hit>,
+    /// The cursor of the next page, if there are more results.
+    pub next_cursor: Option<Cursor>,
+}
+
+/// A page of search results.
+#[derive(Debug, Default)]
+pub struct SearchPage {
+    /// The total number of matching records.
+    pub total_hits: usize,
+    /// The results of the page.
+    pub results: Vec<SearchResult>,
+    /// The cursor of the next page, if there are more results.
+    pub next_cursor: Option<Cursor>,
+    /// The stopwords removed from the query.
+    pub dropped_terms: Vec<String>,
+    /// The spelling suggestions of the unknown query words.
+    pub suggestions: Vec<Suggestion>,
+    /// The query with the unknown words replaced by their best suggestion.
+    pub did_you_mean: Option<String>,
+    /// The terms added to the query.
+    pub expansions: Vec<Expansion>,
+}
+
+/// The information of a record shown in the results.
+pub struct RecordInfo {
+    /// The record id.
+    pub record: i32,
+    /// The TREC id of the record.
+    pub trec_id: String,
+    /// The linked URL.
+    pub url: String,
+    /// The corpus name.
+    pub corpus_name: String,
+}
+
+/// Implementation for `SearchPage` structure.
+impl SearchPage {
+    /// Build the results of a ranked page.
+    ///
+    /// # Arguments
+    ///
+    /// * page          - The ranked page.
+    /// * records       - The information of the records of the page.
+    /// * snippets      - The snippets of the records of the page.
+    /// * explanations  - The explanations of the scores, if asked for.
+    pub fn new(
+        page: RankedPage,
+        records: Vec<RecordInfo>,
+        mut snippets: HashMap<i32, String>,
+        mut explanations: HashMap<i32, Explanation>,
+    ) -> SearchPage {
+        let mut records: HashMap<i32, RecordInfo> =
+            records.into_iter().map(|r| (r.record, r)).collect();
+
+        SearchPage {
+            total_hits: page.total_hits,
+            // Records removed in the meantime are skipped.
+            results: page
+                .hits
+                .into_iter()
+                .filter_map(|hit| {
+                    records.remove(&hit.record).map(|record| SearchResult {
+                        corpus: record.corpus_name,
+                        trec_id: record.trec_id,
+                        url: record.url,
+                        corpus_score: hit.corpus_score,
+                        record_score: hit.record_score,
+                        score_mixed: hit.score_mixed,
+                        snippet: snippets.remove(&hit.record),
+                        explanation: explanations.remove(&hit.record),
+                    })
+                })
+                .collect(),
+            next_cursor: page.next_cursor,
+            dropped_terms: vec![],
+            suggestions: vec![],
+            did_you_mean: None,
+            expansions: vec![],
+        }
+    }
+}
+
+/// Compare two results by their ranking order.
+fn rank_order(a: (f64, i32), b: (f64, i32)) -> Ordering {
+    b.0.total_cmp(&a.0).then_with(|| a.1.cmp(&b.1))
+}
+
+/// Select the candidates of a page, without sorting all of them.
+///
+/// # Arguments
+///
+/// * candidates    - The scored candidates, in any order.
+/// * page          - The requested page.
+/// * key           - Get the score and the record id of a candidate.
+///
+/// # Returns
+///
+/// * The total number of candidates, the candidates of the page (sorted) and the cursor of the
+///   next page.
+pub fn select_page<T, F>(
+    mut candidates: Vec<T>,
+    page: &Page,
+    key: F,
+) -> (usize, Vec<T>, Option<Cursor>)
+where
+    F: Fn(&T) -> (f64, i32),
+{
+    let total_hits = candidates.len();
+
+    let skip = match &page.after {
+        Some(cursor) => {
+            candidates
+                .retain(|c| rank_order(key(c), (cursor.score, cursor.record)) == Ordering::Greater);
+            0
+        }
+        None => page.offset,
+    };
+
+    // Keep the best `skip + limit` candidates, and only sort those.
+    let end = skip.saturating_add(page.limit);
+    let has_more = candidates.len() > end;
+    if has_more {
+        candidates.select_nth_unstable_by(end, |a, b| rank_order(key(a), key(b)));
+        candidates.truncate(end);
+    }
+    candidates.sort_unstable_by(|a, b| rank_order(key(a), key(b)));
+    let candidates: Vec<T> = candidates.into_iter().skip(skip).collect();
+
+    let next_cursor = match (has_more, candidates.last()) {
+        (true, Some(last)) => {
+            let (score, record) = key(last);
+            Some(Cursor { score, record })
+        }
+        _ => None,
+    };
+
+    (total_hits, candidates, next_cursor)
+}
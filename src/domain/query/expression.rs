@@ -0,0 +1,339 @@
+use std::collections::{HashMap, HashSet};
+
+/// A parsed query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// A single term.
+    Term(String),
+    /// Terms that must appear consecutively, in this order.
+    Phrase(Vec<String>),
+    /// Terms that must all appear within a window of `distance` words, in any order.
+    Near { terms: Vec<String>, distance: u32 },
+    /// A filter on a field of the records (e.g. `site:example.org`).
+    Field { field: Field, value: String },
+    /// Matches the records matching all the expressions.
+    And(Vec<Expr>),
+    /// Matches the records matching any of the expressions.
+    Or(Vec<Expr>),
+    /// Matches the records not matching the expression.
+    Not(Box<Expr>),
+    /// Matches the records matching the expression, its terms weigh `boost` times more.
+    Boost { expr: Box<Expr>, boost: f64 },
+    /// A list of clauses with `+`/`-` modifiers.
+    ///
+    /// Matches the records matching all the `required` clauses and none of the `prohibited`.
+    /// Without `required` clauses, any of the `optional` clauses must match, otherwise they only
+    /// count for the scoring.
+    Clauses {
+        required: Vec<Expr>,
+        optional: Vec<Expr>,
+        prohibited: Vec<Expr>,
+    },
+}
+
+/// The fields of the records that can be searched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// `site:`, the host of the URL or one of its parent domains.
+    Site,
+    /// `inurl:`, a part of the URL.
+    InUrl,
+    /// `corpus:`, the name of the corpus.
+    Corpus,
+    /// `meta:`, a part of the metadata of the record.
+    Meta,
+}
+
+/// Implementation for `Field` structure.
+impl Field {
+    /// Get a field by its name in the query.
+    ///
+    /// # Arguments
+    ///
+    /// * name      - The name of the field.
+    pub fn from_name(name: &str) -> Option<Field> {
+        match name {
+            "site" => Some(Field::Site),
+            "inurl" => Some(Field::InUrl),
+            "corpus" => Some(Field::Corpus),
+            "meta" => Some(Field::Meta),
+            _ => None,
+        }
+    }
+}
+
+/// Implementation for `Expr` structure.
+impl Expr {
+    /// Get all the terms of the expression, including the excluded ones.
+    pub fn terms(&self) -> Vec<&str> {
+        let mut terms = vec![];
+        self.visit(&mut |term, _, _| terms.push(term));
+        terms
+    }
+
+    /// Get the terms used for scoring, i.e. the ones not excluded, in query order.
+    pub fn positive_terms(&self) -> Vec<&str> {
+        self.scored_terms()
+            .into_iter()
+            .map(|(term, _)| term)
+            .collect()
+    }
+
+    /// Get the terms used for scoring with their boost, in query order.
+    pub fn scored_terms(&self) -> Vec<(&str, f64)> {
+        let mut terms = vec![];
+        self.visit(&mut |term, negated, boost| {
+            if !negated {
+                terms.push((term, boost))
+            }
+        });
+        terms
+    }
+
+    /// Visit the terms of the expression, along with whether they are excluded and their boost.
+    fn visit<'a, F: FnMut(&'a str, bool, f64)>(&'a self, f: &mut F) {
+        fn walk<'a, F: FnMut(&'a str, bool, f64)>(
+            expr: &'a Expr,
+            negated: bool,
+            boost: f64,
+            f: &mut F,
+        ) {
+            match expr {
+                Expr::Term(term) => f(term, negated, boost),
+                Expr::Phrase(terms) | Expr::Near { terms, .. } => {
+                    terms.iter().for_each(|term| f(term, negated, boost));
+                }
+                Expr::Field { .. } => {}
+                Expr::And(exprs) | Expr::Or(exprs) => {
+                    exprs.iter().for_each(|e| walk(e, negated, boost, f));
+                }
+                Expr::Not(expr) => walk(expr, !negated, boost, f),
+                Expr::Boost { expr, boost: b } => walk(expr, negated, boost * b, f),
+                Expr::Clauses {
+                    required,
+                    optional,
+                    prohibited,
+                } => {
+                    required
+                        .iter()
+                        .chain(optional.iter())
+                        .for_each(|e| walk(e, negated, boost, f));
+                    prohibited.iter().for_each(|e| walk(e, !negated, boost, f));
+                }
+            }
+        }
+        walk(self, false, 1_f64, f)
+    }
+
+    /// Check if the expression only filters by fields.
+    pub fn is_filter(&self) -> bool {
+        match self {
+            Expr::Field { .. } => true,
+            Expr::And(exprs) | Expr::Or(exprs) => exprs.iter().all(|e| e.is_filter()),
+            Expr::Not(expr) | Expr::Boost { expr, .. } => expr.is_filter(),
+            _ => false,
+        }
+    }
+
+    /// Check if the expression matches some records only.
+    ///
+    /// An expression made of exclusions only (e.g. `-car`) matches all the records but the
+    /// excluded ones.
+    pub fn is_bounded(&self) -> bool {
+        match self {
+            Expr::Term(_) | Expr::Phrase(_) | Expr::Near { .. } | Expr::Field { .. } => true,
+            Expr::And(exprs) => exprs.iter().any(|e| e.is_bounded()),
+            Expr::Or(exprs) => exprs.iter().all(|e| e.is_bounded()),
+            Expr::Not(expr) => !expr.is_bounded(),
+            Expr::Boost { expr, .. } => expr.is_bounded(),
+            Expr::Clauses {
+                required, optional, ..
+            } => match required.is_empty() {
+                true if !optional.is_empty() => optional.iter().all(|e| e.is_bounded()),
+                _ => required.iter().any(|e| e.is_bounded()),
+            },
+        }
+    }
+
+    /// Check if the expression is a plain disjunction of terms.
+    ///
+    /// The records matching such an expression are exactly the ones having a posting of its terms,
+    /// so it doesn't need to be evaluated.
+    pub fn is_disjunction(&self) -> bool {
+        match self {
+            Expr::Term(_) => true,
+            Expr::Or(exprs) => exprs.iter().all(|e| e.is_disjunction()),
+            Expr::Boost { expr, .. } => expr.is_disjunction(),
+            _ => false,
+        }
+    }
+
+    /// Remove the given terms (e.g. the stopwords) from the expression.
+    ///
+    /// The removed terms match every record, so an expression left without terms is removed as
+    /// well. Phrases and proximity clauses are kept whole, since their terms are matched by
+    /// position, unless `from_phrases` is set: their other terms must then appear within the
+    /// original window (the length of the phrase, or the `NEAR/n` distance).
+    ///
+    /// # Arguments
+    ///
+    /// * terms         - The terms to remove.
+    /// * from_phrases  - Also remove the terms from the phrases and proximity clauses.
+    pub fn without(self, terms: &HashSet<String>, from_phrases: bool) -> Option<Expr> {
+        let prune = |exprs: Vec<Expr>| -> Vec<Expr> {
+            exprs
+                .into_iter()
+                .filter_map(|e| e.without(terms, from_phrases))
+                .collect()
+        };
+        let window = |words: Vec<String>, distance: u32| -> Option<Expr> {
+            let mut kept: Vec<String> = words.into_iter().filter(|w| !terms.contains(w)).collect();
+            match kept.len() {
+                0 => None,
+                1 => kept.pop().map(Expr::Term),
+                _ => Some(Expr::Near {
+                    terms: kept,
+                    distance,
+                }),
+            }
+        };
+        let simplify = |mut exprs: Vec<Expr>, wrap: fn(Vec<Expr>) -> Expr| match exprs.len() {
+            0 => None,
+            1 => exprs.pop(),
+            _ => Some(wrap(exprs)),
+        };
+
+        match self {
+            Expr::Term(term) => match terms.contains(&term) {
+                true => None,
+                false => Some(Expr::Term(term)),
+            },
+            Expr::Phrase(words) if from_phrases && words.iter().any(|w| terms.contains(w)) => {
+                let distance = words.len() as u32 - 1;
+                window(words, distance)
+            }
+            Expr::Near {
+                terms: words,
+                distance,
+            } if from_phrases => window(words, distance),
+            Expr::Phrase(_) | Expr::Near { .. } | Expr::Field { .. } => Some(self),
+            Expr::And(exprs) => simplify(prune(exprs), Expr::And),
+            Expr::Or(exprs) => simplify(prune(exprs), Expr::Or),
+            Expr::Not(expr) => expr
+                .without(terms, from_phrases)
+                .map(|e| Expr::Not(Box::new(e))),
+            Expr::Boost { expr, boost } => expr.without(terms, from_phrases).map(|e| Expr::Boost {
+                expr: Box::new(e),
+                boost,
+            }),
+            Expr::Clauses {
+                required,
+                optional,
+                prohibited,
+            } => {
+                let (required, optional, prohibited) =
+                    (prune(required), prune(optional), prune(prohibited));
+                if required.is_empty() && optional.is_empty() && prohibited.is_empty() {
+                    return None;
+                }
+                Some(Expr::Clauses {
+                    required,
+                    optional,
+                    prohibited,
+                })
+            }
+        }
+    }
+
+    /// Add the alternatives of the given terms (e.g. the spelling suggestions) to the expression.
+    ///
+    /// Each single term with alternatives is replaced by a disjunction of the term and its
+    /// alternatives. The terms of phrases and proximity clauses, and the excluded terms, are left
+    /// as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * alternatives  - The alternatives of each term.
+    pub fn expand(self, alternatives: &HashMap<String, Vec<String>>) -> Expr {
+        let expand = |exprs: Vec<Expr>| -> Vec<Expr> {
+            exprs.into_iter().map(|e| e.expand(alternatives)).collect()
+        };
+
+        match self {
+            Expr::Term(term) => match alternatives.get(&term) {
+                Some(words) if !words.is_empty() => Expr::Or(
+                    std::iter::once(term)
+                        .chain(words.iter().cloned())
+                        .map(Expr::Term)
+                        .collect(),
+                ),
+                _ => Expr::Term(term),
+            },
+            Expr::Phrase(_) | Expr::Near { .. } | Expr::Field { .. } | Expr::Not(_) => self,
+            Expr::And(exprs) => Expr::And(expand(exprs)),
+            Expr::Or(exprs) => Expr::Or(expand(exprs)),
+            Expr::Boost { expr, boost } => Expr::Boost {
+                expr: Box::new(expr.expand(alternatives)),
+                boost,
+            },
+            Expr::Clauses {
+                required,
+                optional,
+                prohibited,
+            } => Expr::Clauses {
+                required: expand(required),
+                optional: expand(optional),
+                prohibited,
+            },
+        }
+    }
+}
+
+/// Get the smallest window containing a position of every term.
+///
+/// # Arguments
+///
+/// * positions - The sorted positions of each term.
+///
+/// # Returns
+///
+/// * The distance between the first and the last position of the window, or `None` if a term has
+///   no positions.
+pub fn min_span(positions: &[&[i32]]) -> Option<i32> {
+    if positions.iter().any(|p| p.is_empty()) {
+        return None;
+    }
+
+    // All the positions, tagged with their term.
+    let mut merged: Vec<(i32, usize)> = positions
+        .iter()
+        .enumerate()
+        .flat_map(|(term, p)| p.iter().map(move |position| (*position, term)))
+        .collect();
+    merged.sort_unstable();
+
+    // Slide a window over the positions, keeping it minimal while it contains every term.
+    let mut counts = vec![0; positions.len()];
+    let mut missing = positions.len();
+    let mut best: Option<i32> = None;
+    let mut start = 0;
+    for end in 0..merged.len() {
+        let term = merged[end].1;
+        if counts[term] == 0 {
+            missing -= 1;
+        }
+        counts[term] += 1;
+
+        while missing == 0 {
+            let span = merged[end].0 - merged[start].0;
+            best = Some(best.map_or(span, |b| b.min(span)));
+            let term = merged[start].1;
+            counts[term] -= 1;
+            if counts[term] == 0 {
+                missing += 1;
+            }
+            start += 1;
+        }
+    }
+    best
+}
@@ -0,0 +1,9 @@
+/// The query language.
+///
+/// Queries are parsed into an `Expr`, which selects the matching records, while its positive
+/// terms are used for the scoring.
+mod expression;
+mod parser;
+
+pub use expression::*;
+pub use parser::*;
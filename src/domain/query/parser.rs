@@ -0,0 +1,505 @@
+use crate::analysis::Analyzer;
+use crate::domain::query::{Expr, Field};
+use std::fmt;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+/// A query syntax error.
+#[derive(Debug, PartialEq)]
+pub enum QueryError {
+    /// The query ended where a term was expected.
+    UnexpectedEnd,
+    /// An operator was found where a term was expected.
+    UnexpectedOperator(String),
+    /// A parenthesis is not closed, or closed without being opened.
+    UnbalancedParenthesis,
+    /// A quote is not closed.
+    UnclosedQuote,
+    /// A `NEAR/n` operand is not a term.
+    InvalidNearOperand,
+    /// A `^boost` is not a positive number.
+    InvalidBoost(String),
+    /// The groups and modifiers are nested too deeply.
+    TooDeep,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::UnexpectedEnd => write!(f, "The query ended where a term was expected"),
+            QueryError::UnexpectedOperator(op) => write!(f, "Unexpected operator {}", op),
+            QueryError::UnbalancedParenthesis => write!(f, "Unbalanced parenthesis"),
+            QueryError::UnclosedQuote => write!(f, "Unclosed quote"),
+            QueryError::InvalidNearOperand => write!(f, "The operands of NEAR must be terms"),
+            QueryError::InvalidBoost(boost) => {
+                write!(f, "Invalid boost ^{}, it must be a positive number", boost)
+            }
+            QueryError::TooDeep => write!(
+                f,
+                "The query is nested too deeply (at most {} levels)",
+                MAX_DEPTH
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// The maximum nesting of the groups and modifiers (`(`, `-`, `NOT`, `+`) of a query.
+///
+/// The query is parsed recursively, so the nesting must be bounded to keep within the stack.
+const MAX_DEPTH: usize = 32;
+
+/// The terms of a query word or phrase, for each analyzer.
+type Variants = Vec<Vec<String>>;
+
+/// A lexical token of the query.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// `(`
+    Open,
+    /// `)`
+    Close,
+    /// `AND`
+    And,
+    /// `OR`
+    Or,
+    /// `NOT` or `-` in front of a term.
+    Not,
+    /// `+` in front of a term.
+    Required,
+    /// `NEAR/n`
+    Near(u32),
+    /// `^n` after a clause.
+    Boost(f64),
+    /// The terms of a query word.
+    Word(Variants),
+    /// The terms of a quoted phrase.
+    Phrase(Variants),
+    /// A field filter.
+    Field(Field, String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::Required => write!(f, "+"),
+            Token::Near(distance) => write!(f, "NEAR/{}", distance),
+            Token::Boost(boost) => write!(f, "^{}", boost),
+            Token::Word(variants) => write!(f, "{}", first_variant(variants).join(" ")),
+            Token::Phrase(variants) => write!(f, "\"{}\"", first_variant(variants).join(" ")),
+            Token::Field(field, value) => write!(f, "{:?}:{}", field, value),
+        }
+    }
+}
+
+/// A clause of a query, with its modifier.
+enum Clause {
+    /// A clause without modifier.
+    Optional(Expr),
+    /// A `+` clause.
+    Required(Expr),
+    /// A `-` or `NOT` clause.
+    Prohibited(Expr),
+}
+
+/// Implementation for `Clause` structure.
+impl Clause {
+    /// Get the expression of the clause, outside of a clause list.
+    fn into_expr(self) -> Expr {
+        match self {
+            Clause::Optional(expr) | Clause::Required(expr) => expr,
+            Clause::Prohibited(expr) => Expr::Not(Box::new(expr)),
+        }
+    }
+}
+
+/// Get the first non-empty variant of a word.
+fn first_variant(variants: &Variants) -> &[String] {
+    variants
+        .iter()
+        .find(|words| !words.is_empty())
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Analyze a query word or phrase with each analyzer.
+///
+/// # Arguments
+///
+/// * text      - The word or phrase.
+/// * analyzers - The analyzers of the searched corpora.
+///
+/// # Returns
+///
+/// * `None` if none of the analyzers finds a term.
+fn analyze(text: &str, analyzers: &[Box<dyn Analyzer>]) -> Option<Variants> {
+    let variants: Variants = analyzers
+        .iter()
+        .map(|analyzer| analyzer.analyze(text))
+        .collect();
+    match variants.iter().all(Vec::is_empty) {
+        true => None,
+        false => Some(variants),
+    }
+}
+
+/// Join the distinct expressions built from each variant of a word.
+///
+/// # Arguments
+///
+/// * variants  - The terms of the word, for each analyzer.
+/// * build     - Build the expression of a non-empty variant.
+fn variants_expr(variants: Variants, build: impl Fn(Vec<String>) -> Expr) -> Expr {
+    let mut exprs: Vec<Expr> = vec![];
+    for words in variants.into_iter().filter(|words| !words.is_empty()) {
+        let expr = build(words);
+        if !exprs.contains(&expr) {
+            exprs.push(expr);
+        }
+    }
+    match exprs.len() {
+        1 => exprs.remove(0),
+        _ => Expr::Or(exprs),
+    }
+}
+
+/// Get the expression of a word or phrase: a term, or the phrase of its terms.
+fn terms_expr(mut words: Vec<String>) -> Expr {
+    match words.len() {
+        1 => Expr::Term(words.remove(0)),
+        _ => Expr::Phrase(words),
+    }
+}
+
+/// Split a query into tokens.
+///
+/// # Arguments
+///
+/// * query     - The query.
+/// * analyzers - The analyzers of the searched corpora.
+fn lex(query: &str, analyzers: &[Box<dyn Analyzer>]) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = vec![];
+    let mut chars = query.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '-' => {
+                chars.next();
+                tokens.push(Token::Not);
+            }
+            '+' => {
+                chars.next();
+                tokens.push(Token::Required);
+            }
+            '^' => {
+                chars.next();
+                let mut boost = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '"' {
+                        break;
+                    }
+                    boost.push(c);
+                    chars.next();
+                }
+                match boost.parse::<f64>() {
+                    Ok(value) if value.is_finite() && value > 0_f64 => {
+                        tokens.push(Token::Boost(value))
+                    }
+                    _ => return Err(QueryError::InvalidBoost(boost)),
+                }
+            }
+            '"' => {
+                chars.next();
+                let mut phrase = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => phrase.push(c),
+                        None => return Err(QueryError::UnclosedQuote),
+                    }
+                }
+                if let Some(variants) = analyze(&phrase, analyzers) {
+                    tokens.push(Token::Phrase(variants));
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' || c == '^' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+
+                    // A quoted field value, e.g. `meta:"new york"`.
+                    if c == ':' && chars.peek() == Some(&'"') {
+                        chars.next();
+                        loop {
+                            match chars.next() {
+                                Some('"') => break,
+                                Some(c) => word.push(c),
+                                None => return Err(QueryError::UnclosedQuote),
+                            }
+                        }
+                        break;
+                    }
+                }
+
+                if let Some(token) = field_token(&word) {
+                    tokens.push(token);
+                    continue;
+                }
+                let near = word
+                    .strip_prefix("NEAR/")
+                    .and_then(|distance| distance.parse::<u32>().ok());
+                match (word.as_str(), near) {
+                    (_, Some(distance)) => tokens.push(Token::Near(distance)),
+                    ("AND", _) => tokens.push(Token::And),
+                    ("OR", _) => tokens.push(Token::Or),
+                    ("NOT", _) => tokens.push(Token::Not),
+                    _ => {
+                        // Words without any indexed token (e.g. punctuation) are skipped.
+                        if let Some(variants) = analyze(&word, analyzers) {
+                            tokens.push(Token::Word(variants));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Get the field filter of a query word (`field:value`).
+///
+/// # Arguments
+///
+/// * word      - The query word.
+fn field_token(word: &str) -> Option<Token> {
+    let (name, value) = word.split_once(':')?;
+    let field = Field::from_name(name)?;
+    if value.trim().is_empty() {
+        return None;
+    }
+    let value = match field {
+        Field::Corpus => String::from(value),
+        _ => value.to_lowercase(),
+    };
+    Some(Token::Field(field, value))
+}
+
+/// Parse a query.
+///
+/// The query is a list of clauses, any of which must match. A clause is one of:
+///
+/// * `term`, a word (split in several terms like the indexed text, matched as a phrase).
+/// * `"a phrase"`, terms that must appear consecutively.
+/// * `term NEAR/n term`, terms that must appear within a window of `n` words.
+/// * `site:host`, `inurl:text`, `corpus:name` or `meta:text`, a filter on a field of the records.
+///   Along with other clauses, the filters must match.
+/// * `+clause`, a clause that must match.
+/// * `-clause` or `NOT clause`, a clause that must not match.
+/// * `(query)`, a group.
+/// * `clause^n`, a clause whose terms weigh `n` times more in the scoring (e.g. `jaguar^2.5`).
+/// * `clause AND clause`, both clauses must match.
+///
+/// `OR` may be used between clauses for readability.
+///
+/// The words are analyzed with each of the given analyzers, and match any of the resulting terms.
+///
+/// # Arguments
+///
+/// * query     - The query.
+/// * analyzers - The analyzers of the searched corpora.
+///
+/// # Returns
+///
+/// * `None` if the query has no terms.
+pub fn parse_query(
+    query: &str,
+    analyzers: &[Box<dyn Analyzer>],
+) -> Result<Option<Expr>, QueryError> {
+    let mut tokens = lex(query, analyzers)?.into_iter().peekable();
+    let expr = parse_clauses(&mut tokens, 0)?;
+    match tokens.next() {
+        Some(Token::Close) => Err(QueryError::UnbalancedParenthesis),
+        Some(token) => Err(QueryError::UnexpectedOperator(token.to_string())),
+        None => Ok(expr),
+    }
+}
+
+/// Parse a list of clauses, up to the end of the query or of the group.
+///
+/// # Arguments
+///
+/// * tokens    - The tokens of the query.
+/// * depth     - The nesting of the clauses.
+fn parse_clauses(
+    tokens: &mut Peekable<IntoIter<Token>>,
+    depth: usize,
+) -> Result<Option<Expr>, QueryError> {
+    let mut clauses = vec![];
+    while let Some(token) = tokens.peek() {
+        match token {
+            Token::Close => break,
+            Token::Or if !clauses.is_empty() => {
+                tokens.next();
+                if matches!(tokens.peek(), None | Some(Token::Close)) {
+                    return Err(QueryError::UnexpectedEnd);
+                }
+            }
+            _ => clauses.push(parse_and(tokens, depth)?),
+        }
+    }
+
+    // The filters restrict the other clauses.
+    let restricts = clauses.iter().any(|clause| match clause {
+        Clause::Optional(expr) | Clause::Required(expr) => !expr.is_filter(),
+        Clause::Prohibited(_) => false,
+    });
+
+    let (mut required, mut optional, mut prohibited) = (vec![], vec![], vec![]);
+    for clause in clauses {
+        match clause {
+            Clause::Optional(expr) if restricts && expr.is_filter() => required.push(expr),
+            Clause::Optional(expr) => optional.push(expr),
+            Clause::Required(expr) => required.push(expr),
+            Clause::Prohibited(expr) => prohibited.push(expr),
+        }
+    }
+
+    Ok(
+        match (required.is_empty() && prohibited.is_empty(), optional.len()) {
+            (true, 0) => None,
+            (true, 1) => optional.pop(),
+            (true, _) => Some(Expr::Or(optional)),
+            (false, _) => Some(Expr::Clauses {
+                required,
+                optional,
+                prohibited,
+            }),
+        },
+    )
+}
+
+/// Parse clauses joined with `AND`.
+fn parse_and(tokens: &mut Peekable<IntoIter<Token>>, depth: usize) -> Result<Clause, QueryError> {
+    let first = parse_clause(tokens, depth)?;
+    if tokens.peek() != Some(&Token::And) {
+        return Ok(first);
+    }
+
+    let mut exprs = vec![first.into_expr()];
+    while tokens.peek() == Some(&Token::And) {
+        tokens.next();
+        exprs.push(parse_clause(tokens, depth)?.into_expr());
+    }
+    Ok(Clause::Optional(Expr::And(exprs)))
+}
+
+/// Parse a single clause, with its boost.
+fn parse_clause(
+    tokens: &mut Peekable<IntoIter<Token>>,
+    depth: usize,
+) -> Result<Clause, QueryError> {
+    let clause = parse_unboosted(tokens, depth)?;
+    let boost = match tokens.peek() {
+        Some(Token::Boost(boost)) => *boost,
+        _ => return Ok(clause),
+    };
+    tokens.next();
+
+    let boosted = |expr: Expr| Expr::Boost {
+        expr: Box::new(expr),
+        boost,
+    };
+    Ok(match clause {
+        Clause::Optional(expr) => Clause::Optional(boosted(expr)),
+        Clause::Required(expr) => Clause::Required(boosted(expr)),
+        Clause::Prohibited(expr) => Clause::Prohibited(expr),
+    })
+}
+
+/// Parse a single clause, without its boost.
+fn parse_unboosted(
+    tokens: &mut Peekable<IntoIter<Token>>,
+    depth: usize,
+) -> Result<Clause, QueryError> {
+    let nested = match tokens.peek() {
+        Some(Token::Not | Token::Required | Token::Open) if depth >= MAX_DEPTH => {
+            return Err(QueryError::TooDeep)
+        }
+        _ => depth + 1,
+    };
+    match tokens.next() {
+        None => Err(QueryError::UnexpectedEnd),
+        Some(Token::Not) => Ok(Clause::Prohibited(
+            parse_clause(tokens, nested)?.into_expr(),
+        )),
+        Some(Token::Required) => Ok(Clause::Required(parse_clause(tokens, nested)?.into_expr())),
+        Some(Token::Open) => {
+            let expr = parse_clauses(tokens, nested)?;
+            if tokens.next() != Some(Token::Close) {
+                return Err(QueryError::UnbalancedParenthesis);
+            }
+            expr.map(Clause::Optional).ok_or(QueryError::UnexpectedEnd)
+        }
+        Some(Token::Word(variants))
+            if tokens.peek().is_some_and(|t| matches!(t, Token::Near(_))) =>
+        {
+            parse_near(variants, tokens).map(Clause::Optional)
+        }
+        Some(Token::Word(variants)) | Some(Token::Phrase(variants)) => {
+            Ok(Clause::Optional(variants_expr(variants, terms_expr)))
+        }
+        Some(Token::Field(field, value)) => Ok(Clause::Optional(Expr::Field { field, value })),
+        Some(token) => Err(QueryError::UnexpectedOperator(token.to_string())),
+    }
+}
+
+/// Parse terms joined with `NEAR/n`.
+///
+/// A chain of terms must appear within the largest of the given distances.
+///
+/// # Arguments
+///
+/// * first     - The terms of the first operand, for each analyzer.
+fn parse_near(first: Variants, tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expr, QueryError> {
+    let mut variants = first;
+    let mut distance = 0;
+    while let Some(Token::Near(n)) = tokens.peek() {
+        distance = distance.max(*n);
+        tokens.next();
+        match tokens.next() {
+            Some(Token::Word(words)) => {
+                for (terms, words) in variants.iter_mut().zip(words) {
+                    terms.extend(words);
+                }
+            }
+            Some(_) => return Err(QueryError::InvalidNearOperand),
+            None => return Err(QueryError::UnexpectedEnd),
+        }
+    }
+    Ok(variants_expr(variants, |terms| Expr::Near {
+        terms,
+        distance,
+    }))
+}
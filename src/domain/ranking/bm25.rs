@@ -0,0 +1,176 @@
+use crate::configuration::Bm25Settings;
+use crate::domain::ranking::{Ranker, Scorer};
+use crate::domain::{Explanation, Hit};
+use crate::services::{CollectionStats, Posting, QueryWord};
+use std::collections::HashMap;
+
+/// The Okapi BM25 model.
+///
+/// The document length is `record_index.total_words`, and the IDF of a term is
+/// `ln(1 + (N - n + 0.5) / (n + 0.5))`, where `N` is the number of records and `n` the number of
+/// records containing the term. The IDF is multiplied by the weight of the term in the query, and
+/// the score of the term in a record by the boost of the record.
+pub struct Bm25Ranker {
+    /// The BM25 `k1` and `b` parameters.
+    pub params: Bm25Settings,
+}
+
+impl Ranker for Bm25Ranker {
+    fn requires_stats(&self) -> bool {
+        true
+    }
+
+    fn scorer<'a>(
+        &self,
+        words: &'a [QueryWord],
+        stats: Option<&CollectionStats>,
+    ) -> Box<dyn Scorer + 'a> {
+        let (total_records, average_length) = match stats {
+            Some(stats) => (stats.total_records as f64, stats.average_length),
+            None => (0_f64, 0_f64),
+        };
+
+        Box::new(Bm25Scorer {
+            words,
+            idf: words
+                .iter()
+                .map(|word| {
+                    let df = word.records as f64;
+                    word.weight * (1_f64 + (total_records - df + 0.5) / (df + 0.5)).ln()
+                })
+                .collect(),
+            total_records,
+            average_length: if average_length > 0_f64 {
+                average_length
+            } else {
+                1_f64
+            },
+            params: self.params,
+            records: HashMap::new(),
+        })
+    }
+}
+
+/// The BM25 accumulator.
+struct Bm25Scorer<'a> {
+    /// The query words.
+    words: &'a [QueryWord],
+    /// The IDF of each query word.
+    idf: Vec<f64>,
+    /// The number of records.
+    total_records: f64,
+    /// The average record length.
+    average_length: f64,
+    /// The BM25 `k1` and `b` parameters.
+    params: Bm25Settings,
+    /// The accumulated score of the records seen so far.
+    records: HashMap<i32, f64>,
+}
+
+/// Implementation for `Bm25Scorer` structure.
+impl Bm25Scorer<'_> {
+    /// The length normalization of a record, `k1 * (1 - b + b * length / average_length)`.
+    ///
+    /// # Arguments
+    ///
+    /// * posting   - A posting of the record.
+    fn norm(&self, posting: &Posting) -> f64 {
+        let length = posting.total_words as f64;
+        self.params.k1 * (1_f64 - self.params.b + self.params.b * length / self.average_length)
+    }
+
+    /// The score of a posting.
+    ///
+    /// # Arguments
+    ///
+    /// * posting   - The posting.
+    fn score(&self, posting: &Posting) -> f64 {
+        let tf = posting.appearances as f64;
+        let word = posting.word as usize;
+        self.idf[word] * tf * (self.params.k1 + 1_f64) / (tf + self.norm(posting))
+            * self.words[word].record_boost(posting.record)
+    }
+}
+
+impl Scorer for Bm25Scorer<'_> {
+    fn add(&mut self, posting: &Posting) {
+        let score = self.score(posting);
+        *self.records.entry(posting.record).or_insert(0_f64) += score;
+    }
+
+    fn hits(self: Box<Self>) -> Vec<Hit> {
+        self.records
+            .into_iter()
+            .map(|(record, score)| Hit {
+                record,
+                corpus_score: 0_f64,
+                record_score: score,
+                score_mixed: score,
+            })
+            .collect()
+    }
+    fn explain(&self, record: i32, postings: &[Posting]) -> Explanation {
+        let terms: Vec<Explanation> = postings
+            .iter()
+            .map(|posting| {
+                let word = &self.words[posting.word as usize];
+                let tf = posting.appearances as f64;
+                let norm = self.norm(posting);
+                let mut details = vec![
+                    Explanation::with(
+                        self.idf[posting.word as usize],
+                        "idf, query_weight * ln(1 + (N - n + 0.5) / (n + 0.5)) from:",
+                        vec![
+                            Explanation::new(word.weight, "query_weight"),
+                            Explanation::new(self.total_records, "N, the number of records"),
+                            Explanation::new(
+                                word.records as f64,
+                                "n, the number of records containing the term",
+                            ),
+                        ],
+                    ),
+                    Explanation::with(
+                        tf * (self.params.k1 + 1_f64),
+                        "numerator, tf * (k1 + 1) from:",
+                        vec![
+                            Explanation::new(tf, "tf, the appearances of the term"),
+                            Explanation::new(self.params.k1, "k1"),
+                        ],
+                    ),
+                    Explanation::with(
+                        tf + norm,
+                        "denominator, tf + k1 * (1 - b + b * length / average_length) from:",
+                        vec![
+                            Explanation::new(tf, "tf, the appearances of the term"),
+                            Explanation::new(self.params.k1, "k1"),
+                            Explanation::new(self.params.b, "b"),
+                            Explanation::new(
+                                posting.total_words as f64,
+                                "length, the words of the record",
+                            ),
+                            Explanation::new(self.average_length, "average_length"),
+                        ],
+                    ),
+                ];
+                let boost = word.record_boost(record);
+                let description = match boost == 1_f64 {
+                    true => format!("weight({}), idf * numerator / denominator", word.word),
+                    false => {
+                        details.push(Explanation::new(boost, "boost, of the title and URL"));
+                        format!(
+                            "weight({}), idf * numerator / denominator * boost",
+                            word.word
+                        )
+                    }
+                };
+                Explanation::with(self.score(posting), description, details)
+            })
+            .collect();
+
+        Explanation::with(
+            terms.iter().map(|term| term.value).sum(),
+            "score, sum of:",
+            terms,
+        )
+    }
+}
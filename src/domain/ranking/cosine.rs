@@ -0,0 +1,265 @@
+use crate::domain::ranking::{Ranker, Scorer};
+use crate::domain::{Explanation, Hit};
+use crate::services::{CollectionStats, Posting, QueryWord};
+use std::collections::HashMap;
+
+/// Cosine similarity over the stored TF-IDF, mixed with the similarity of the record's corpus.
+///
+/// The mixed score is `(1 - record_score) * log2(corpus_score)`. The TF-IDF of a term in a record
+/// is multiplied by the boost of the record.
+pub struct CosineCorpusRanker;
+
+impl Ranker for CosineCorpusRanker {
+    fn scorer<'a>(
+        &self,
+        words: &'a [QueryWord],
+        _stats: Option<&CollectionStats>,
+    ) -> Box<dyn Scorer + 'a> {
+        Box::new(CosineScorer::new(words, true))
+    }
+}
+
+/// Cosine similarity over the stored TF-IDF of the records only.
+pub struct RecordCosineRanker;
+
+impl Ranker for RecordCosineRanker {
+    fn scorer<'a>(
+        &self,
+        words: &'a [QueryWord],
+        _stats: Option<&CollectionStats>,
+    ) -> Box<dyn Scorer + 'a> {
+        Box::new(CosineScorer::new(words, false))
+    }
+}
+
+/// A record ranking temp helper.
+struct RecordRanking {
+    /// The corpus id.
+    corpus_id: i32,
+    /// The numerator.
+    x: f64,
+    /// The denominator.
+    y: f64,
+}
+
+/// A corpus ranking helper.
+struct CorpusRanking {
+    /// The numerator.
+    x: f64,
+    /// The corpus part of the denominator.
+    y: f64,
+    /// The similarity of the corpus.
+    rank: f64,
+}
+
+/// The cosine similarity of a numerator and the squared norms of both vectors.
+///
+/// The similarity is 0 if either norm is 0 (e.g. terms without any weight), instead of NaN.
+///
+/// # Arguments
+///
+/// * x             - The numerator, the dot product of the vectors.
+/// * query_norm    - The squared norm of the query.
+/// * norm          - The squared norm of the record (or corpus).
+fn similarity(x: f64, query_norm: f64, norm: f64) -> f64 {
+    let denominator = query_norm.sqrt() * norm.sqrt();
+    match denominator == 0_f64 {
+        true => 0_f64,
+        false => x / denominator,
+    }
+}
+
+/// The cosine similarity accumulator.
+struct CosineScorer<'a> {
+    /// The query words.
+    words: &'a [QueryWord],
+    /// Mix the record score with the corpus score.
+    mixed: bool,
+    /// The query part of the denominator, for each corpus.
+    denominator: HashMap<i32, f64>,
+    /// The rank of each corpus.
+    corpus_rank: HashMap<i32, CorpusRanking>,
+    /// The records seen so far.
+    records: HashMap<i32, RecordRanking>,
+}
+
+/// Implementation for `CosineScorer` structure.
+impl<'a> CosineScorer<'a> {
+    /// Create a new `CosineScorer` instance, ranking the corpora.
+    ///
+    /// # Arguments
+    ///
+    /// * words     - The query words.
+    /// * mixed     - Mix the record score with the corpus score.
+    fn new(words: &'a [QueryWord], mixed: bool) -> CosineScorer<'a> {
+        let mut denominator: HashMap<i32, f64> = HashMap::new();
+        // The numerator and denominator of each corpus.
+        let mut corpus: HashMap<i32, (f64, f64)> = HashMap::new();
+
+        for word in words.iter() {
+            for info in word.word_corpus_tf_idf.iter() {
+                let weight = word.tfidf.get(&info.corpus_id).copied().unwrap_or_default();
+                let tf_idf = info.tf_idf.unwrap_or_default();
+                *denominator.entry(info.corpus_id).or_default() += weight.powi(2);
+                let (x, y) = corpus.entry(info.corpus_id).or_default();
+                *x += weight * tf_idf;
+                *y += tf_idf.powi(2);
+            }
+        }
+
+        let corpus_rank = corpus
+            .into_iter()
+            .map(|(id, (x, y))| {
+                let rank = similarity(x, denominator[&id], y);
+                (id, CorpusRanking { x, y, rank })
+            })
+            .collect();
+
+        CosineScorer {
+            words,
+            mixed,
+            denominator,
+            corpus_rank,
+            records: HashMap::new(),
+        }
+    }
+
+    /// The query weight of the word of a posting, and its boosted TF-IDF in the record.
+    ///
+    /// # Arguments
+    ///
+    /// * posting   - The posting.
+    fn weights(&self, posting: &Posting) -> (f64, f64) {
+        let word = &self.words[posting.word as usize];
+        let weight = word
+            .tfidf
+            .get(&posting.corpus_id)
+            .copied()
+            .unwrap_or_default();
+        (weight, posting.tf_idf * word.record_boost(posting.record))
+    }
+}
+
+impl Scorer for CosineScorer<'_> {
+    fn add(&mut self, posting: &Posting) {
+        let (weight, tf_idf) = self.weights(posting);
+        let record = self.records.entry(posting.record).or_insert(RecordRanking {
+            corpus_id: posting.corpus_id,
+            x: 0_f64,
+            y: 0_f64,
+        });
+        record.x += weight * tf_idf;
+        record.y += tf_idf.powi(2);
+    }
+
+    fn hits(self: Box<Self>) -> Vec<Hit> {
+        self.records
+            .into_iter()
+            .map(|(id, record)| {
+                let denominator = self
+                    .denominator
+                    .get(&record.corpus_id)
+                    .copied()
+                    .unwrap_or_default();
+                let rank = similarity(record.x, denominator, record.y);
+                match self.mixed {
+                    true => {
+                        // Get the rank of the related corpus (based on the ID).
+                        let corpus_rank = self
+                            .corpus_rank
+                            .get(&record.corpus_id)
+                            .map_or(0_f64, |corpus| corpus.rank);
+                        Hit {
+                            record: id,
+                            corpus_score: corpus_rank,
+                            record_score: rank,
+                            score_mixed: (1_f64 - rank) * corpus_rank.log2(),
+                        }
+                    }
+                    false => Hit {
+                        record: id,
+                        corpus_score: 0_f64,
+                        record_score: rank,
+                        score_mixed: rank,
+                    },
+                }
+            })
+            .collect()
+    }
+    fn explain(&self, _record: i32, postings: &[Posting]) -> Explanation {
+        let corpus_id = postings.first().map_or(0, |posting| posting.corpus_id);
+        let denominator = self
+            .denominator
+            .get(&corpus_id)
+            .copied()
+            .unwrap_or_default();
+
+        // The terms of the numerator, and the record part of the denominator.
+        let mut terms = vec![];
+        let mut y = 0_f64;
+        for posting in postings {
+            let word = &self.words[posting.word as usize];
+            let (weight, tf_idf) = self.weights(posting);
+            let boost = word.record_boost(posting.record);
+            let mut details = vec![
+                Explanation::new(
+                    weight,
+                    "query_weight, boost / query length * idf of the corpus",
+                ),
+                Explanation::new(posting.tf, "tf, stored"),
+                Explanation::new(posting.idf, "idf, stored"),
+            ];
+            let description = match boost == 1_f64 {
+                true => format!("weight({}), query_weight * tf * idf", word.word),
+                false => {
+                    details.push(Explanation::new(boost, "boost, of the title and URL"));
+                    format!("weight({}), query_weight * tf * idf * boost", word.word)
+                }
+            };
+            terms.push(Explanation::with(weight * tf_idf, description, details));
+            y += tf_idf.powi(2);
+        }
+        let x = terms.iter().map(|term| term.value).sum::<f64>();
+        let rank = similarity(x, denominator, y);
+        let record_score = Explanation::with(
+            rank,
+            "record_score, numerator / (sqrt(query_norm) * sqrt(record_norm)) from:",
+            vec![
+                Explanation::with(x, "numerator, sum of:", terms),
+                Explanation::new(
+                    denominator,
+                    "query_norm, sum of the squared query weights in the corpus",
+                ),
+                Explanation::new(y, "record_norm, sum of the squared tf * idf * boost"),
+            ],
+        );
+        if !self.mixed {
+            return record_score;
+        }
+
+        let (corpus_x, corpus_y, corpus_rank) = self
+            .corpus_rank
+            .get(&corpus_id)
+            .map_or((0_f64, 0_f64, 0_f64), |c| (c.x, c.y, c.rank));
+        let corpus_score = Explanation::with(
+            corpus_rank,
+            "corpus_score, numerator / (sqrt(query_norm) * sqrt(corpus_norm)) from:",
+            vec![
+                Explanation::new(
+                    corpus_x,
+                    "numerator, sum of query_weight * tf * idf in the corpus",
+                ),
+                Explanation::new(
+                    denominator,
+                    "query_norm, sum of the squared query weights in the corpus",
+                ),
+                Explanation::new(corpus_y, "corpus_norm, sum of the squared tf * idf"),
+            ],
+        );
+        Explanation::with(
+            (1_f64 - rank) * corpus_rank.log2(),
+            "score_mixed, (1 - record_score) * log2(corpus_score) from:",
+            vec![record_score, corpus_score],
+        )
+    }
+}
@@ -0,0 +1,74 @@
+/// The scoring strategies.
+///
+/// Each strategy implements `Ranker`, and is selected by its `RankingModel`.
+mod bm25;
+mod cosine;
+mod proximity;
+
+pub use bm25::*;
+pub use cosine::*;
+pub use proximity::*;
+
+use crate::configuration::{RankingModel, SearchSettings};
+use crate::domain::{Explanation, Hit};
+use crate::services::{CollectionStats, Posting, QueryWord};
+
+/// A scoring strategy.
+///
+/// Creates a `Scorer` for each query, which is fed with the postings of the query words as they
+/// are streamed from the database.
+pub trait Ranker: Send + Sync {
+    /// Whether the ranker needs the collection statistics (record count, average length).
+    fn requires_stats(&self) -> bool {
+        false
+    }
+
+    /// Create the scorer of a query.
+    ///
+    /// # Arguments
+    ///
+    /// * words     - The query words, with their query weights.
+    /// * stats     - The collection statistics, if `requires_stats` is set.
+    fn scorer<'a>(
+        &self,
+        words: &'a [QueryWord],
+        stats: Option<&CollectionStats>,
+    ) -> Box<dyn Scorer + 'a>;
+}
+
+/// The score accumulator of a single query.
+pub trait Scorer: Send {
+    /// Accumulate a posting of a query word.
+    ///
+    /// # Arguments
+    ///
+    /// * posting   - The posting.
+    fn add(&mut self, posting: &Posting);
+
+    /// Get the scored records, in any order.
+    fn hits(self: Box<Self>) -> Vec<Hit>;
+
+    /// Explain the score of a record.
+    ///
+    /// # Arguments
+    ///
+    /// * record    - The record id.
+    /// * postings  - The postings of the record, as they were accumulated.
+    fn explain(&self, record: i32, postings: &[Posting]) -> Explanation;
+}
+
+/// Get the ranker of a ranking model.
+///
+/// # Arguments
+///
+/// * model     - The ranking model.
+/// * settings  - The search settings, holding the parameters of the models.
+pub fn get_ranker(model: RankingModel, settings: &SearchSettings) -> Box<dyn Ranker> {
+    match model {
+        RankingModel::Cosine => Box::new(CosineCorpusRanker),
+        RankingModel::RecordCosine => Box::new(RecordCosineRanker),
+        RankingModel::Bm25 => Box::new(Bm25Ranker {
+            params: settings.bm25,
+        }),
+    }
+}
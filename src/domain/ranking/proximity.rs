@@ -0,0 +1,118 @@
+use crate::domain::query::min_span;
+use crate::domain::{Explanation, Hit};
+use crate::services::{Posting, QueryWord};
+use std::collections::HashMap;
+
+/// The proximity boost.
+///
+/// Collects the positions of the query words in the scored records, and boosts the records where
+/// two different query words appear close to each other. The boost is added to `score_mixed`,
+/// and is `boost / d`, where `d` is the smallest distance between two different query words.
+pub struct Proximity {
+    /// The distinct word of each query word.
+    words: Vec<usize>,
+    /// The positions of each distinct word in each record.
+    records: HashMap<i32, HashMap<usize, Vec<i32>>>,
+}
+
+/// Implementation for `Proximity` structure.
+impl Proximity {
+    /// Create a new `Proximity` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * words     - The query words.
+    pub fn new(words: &[QueryWord]) -> Proximity {
+        Proximity {
+            words: words
+                .iter()
+                .map(|word| {
+                    words
+                        .iter()
+                        .position(|w| w.word == word.word)
+                        .unwrap_or_default()
+                })
+                .collect(),
+            records: HashMap::new(),
+        }
+    }
+
+    /// Collect the positions of a posting.
+    ///
+    /// # Arguments
+    ///
+    /// * posting   - The posting.
+    pub fn add(&mut self, posting: &Posting) {
+        let word = self.words[posting.word as usize];
+        self.records
+            .entry(posting.record)
+            .or_default()
+            .entry(word)
+            .or_insert_with(|| posting.positions.clone());
+    }
+
+    /// The smallest distance between two different query words in a record.
+    ///
+    /// # Arguments
+    ///
+    /// * record    - The record id.
+    fn distance(&self, record: i32) -> Option<i32> {
+        let words = match self.records.get(&record) {
+            Some(words) if words.len() > 1 => words,
+            _ => return None,
+        };
+        let positions: Vec<&[i32]> = words.values().map(Vec::as_slice).collect();
+
+        // The smallest window containing any two of the words.
+        (0..positions.len())
+            .flat_map(|a| (a + 1..positions.len()).map(move |b| (a, b)))
+            .filter_map(|(a, b)| min_span(&[positions[a], positions[b]]))
+            .min()
+            .filter(|d| *d > 0)
+    }
+
+    /// Boost the scores of the hits.
+    ///
+    /// # Arguments
+    ///
+    /// * hits      - The scored records.
+    /// * boost     - The boost of two adjacent words.
+    pub fn boost(&self, hits: &mut [Hit], boost: f64) {
+        for hit in hits.iter_mut() {
+            if let Some(distance) = self.distance(hit.record) {
+                hit.score_mixed += boost / distance as f64;
+            }
+        }
+    }
+
+    /// Add the proximity boost to the explanation of a score.
+    ///
+    /// # Arguments
+    ///
+    /// * record        - The record id.
+    /// * explanation   - The explanation of the score.
+    /// * boost         - The boost of two adjacent words.
+    pub fn explain(&self, record: i32, explanation: Explanation, boost: f64) -> Explanation {
+        match self.distance(record) {
+            Some(distance) => {
+                let proximity = Explanation::with(
+                    boost / distance as f64,
+                    "proximity, boost / distance from:",
+                    vec![
+                        Explanation::new(boost, "boost"),
+                        Explanation::new(
+                            distance as f64,
+                            "distance, between the closest two query words",
+                        ),
+                    ],
+                );
+                Explanation::with(
+                    explanation.value + proximity.value,
+                    "score_mixed, sum of:",
+                    vec![explanation, proximity],
+                )
+            }
+            None => explanation,
+        }
+    }
+}
@@ -1,16 +1,30 @@
-use crate::services::QueryWord;
 use serde::Serialize;
-use std::collections::HashMap;
 
 /// A simple response container.
 ///
 /// Contains the stats of the returned results.
 #[derive(Debug, Serialize)]
 pub struct ResponseContainer {
-    /// The result container.
+    /// The result container (the requested page).
     pub result: Vec<SearchResult>,
     /// The total size of the response.
     pub result_count: usize,
+    /// The total number of matching records.
+    pub total_hits: usize,
+    /// The offset of the page.
+    pub offset: usize,
+    /// The maximum size of the page.
+    pub limit: usize,
+    /// The cursor of the next page, if there are more results.
+    pub next_cursor: Option<String>,
+    /// The stopwords removed from the query.
+    pub dropped_terms: Vec<String>,
+    /// The indexed words close to the unknown query words.
+    pub suggestions: Vec<Suggestion>,
+    /// The query with the unknown words replaced by their best suggestion.
+    pub did_you_mean: Option<String>,
+    /// The terms added to the query.
+    pub expansions: Vec<Expansion>,
     /// The search duration.
     pub duration: u128,
 }
@@ -32,156 +46,130 @@ pub struct SearchResult {
     pub record_score: f64,
     /// The combined result of corpus and record.
     pub score_mixed: f64,
+    /// The best matching part of the record, with the query terms highlighted.
+    pub snippet: Option<String>,
+    /// How the score was computed, only when asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<Explanation>,
 }
 
-/// A record ranking temp helper.
-pub struct RecordRanking {
-    /// The related corpus name.
-    corpus_name: String,
-    /// The corpus id.
-    corpus_id: i32,
-    /// The related url.
-    url: String,
-    /// The trec id of the document.
-    trec_id: String,
-    /// The final rank.
-    rank: f64,
-    /// The numerator.
-    x: f64,
-    /// The denominator.
-    y: f64,
-}
-
-/// A corpus ranking temp helper.
-pub struct CorpusRanking {
-    /// Name of the corpus (also used to identify it).
-    corpus_name: String,
-    /// Corpus id.
-    corpus_id: i32,
-    /// The final rank.
-    rank: f64,
-    /// The numerator.
-    x: f64,
-    /// The denominator.
-    y: f64,
+/// The explanation of a score, or of a part of it.
+///
+/// A tree like the explanations of Lucene: the value of a node is computed from the values of
+/// its details, as told by its description.
+#[derive(Debug, Clone, Serialize, PartialEq, PartialOrd)]
+pub struct Explanation {
+    /// The value.
+    pub value: f64,
+    /// What the value is, and how it is computed.
+    pub description: String,
+    /// The values it is computed from.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<Explanation>,
 }
 
-/// Build and calculate the rank of the returned results.
-pub fn build_result(word_idx: Vec<QueryWord>) -> Vec<SearchResult> {
-    // Temp holders.
-    let mut record_ranking: Vec<RecordRanking> = vec![];
-    let mut corpus_ranking: Vec<CorpusRanking> = vec![];
-
-    // The denominator for each corpus.
-    let mut denominator: HashMap<i32, f64> = HashMap::new();
-
-    for word in word_idx.iter() {
-        for corpus in word.tfidf.clone() {
-            // Init to 0 (f64).
-            denominator.insert(corpus.0, 0_f64);
+/// Implementation for `Explanation` structure.
+impl Explanation {
+    /// Create a new `Explanation` instance, without details.
+    ///
+    /// # Arguments
+    ///
+    /// * value         - The value.
+    /// * description   - What the value is.
+    pub fn new(value: f64, description: impl Into<String>) -> Explanation {
+        Explanation {
+            value,
+            description: description.into(),
+            details: vec![],
         }
     }
 
-    // Create the indexes for record and corpus.
-    for word in word_idx.iter() {
-        // Corpus.
-        for corpus in word.word_corpus_tf_idf.iter() {
-            denominator.insert(
-                corpus.corpus_id,
-                denominator[&corpus.corpus_id] + word.tfidf.get(&corpus.corpus_id).unwrap().powi(2),
-            );
-            if corpus_ranking
-                .iter()
-                .any(|x| x.corpus_name == corpus.corpus_name)
-            {
-                // The corpus ranking is already initiated. Update it.
-                corpus_ranking
-                    .iter_mut()
-                    .find(|x| x.corpus_name == corpus.corpus_name)
-                    .map(|x| {
-                        x.x += word.tfidf.get(&corpus.corpus_id).unwrap() * corpus.tf_idf.unwrap();
-                        x.y += corpus.tf_idf.unwrap().powi(2);
-                    });
-            } else {
-                // Append the corpus.
-                corpus_ranking.push(CorpusRanking {
-                    corpus_name: String::from(&corpus.corpus_name),
-                    corpus_id: corpus.corpus_id,
-                    rank: 0_f64,
-                    x: word.tfidf.get(&corpus.corpus_id).unwrap() * corpus.tf_idf.unwrap(),
-                    y: corpus.tf_idf.unwrap().powi(2),
-                });
-            }
-        }
-
-        // Record.
-        for record in word.word_record_tf_idf.iter() {
-            if record_ranking.iter().any(|x| x.trec_id == record.trec_id) {
-                // Update the record.
-                record_ranking
-                    .iter_mut()
-                    .find(|x| x.trec_id == record.trec_id)
-                    .map(|x| {
-                        x.x += word.tfidf.get(&record.corpus_id).unwrap() * record.tf_idf.unwrap();
-                        x.y += record.tf_idf.unwrap().powi(2);
-                    });
-            } else {
-                // Append a new record.
-                record_ranking.push(RecordRanking {
-                    corpus_id: record.corpus_id,
-                    url: String::from(&record.url),
-                    corpus_name: String::from(&record.corpus_name),
-                    trec_id: String::from(&record.trec_id),
-                    rank: 0_f64,
-                    x: word.tfidf.get(&record.corpus_id).unwrap() * record.tf_idf.unwrap(),
-                    y: record.tf_idf.unwrap().powi(2),
-                });
-            }
+    /// Create a new `Explanation` instance, computed from other values.
+    ///
+    /// # Arguments
+    ///
+    /// * value         - The value.
+    /// * description   - What the value is, and how it is computed.
+    /// * details       - The values it is computed from.
+    pub fn with(
+        value: f64,
+        description: impl Into<String>,
+        details: Vec<Explanation>,
+    ) -> Explanation {
+        Explanation {
+            value,
+            description: description.into(),
+            details,
         }
     }
+}
 
-    // Rank for Corpus
-    for corpus in corpus_ranking.iter_mut() {
-        corpus.rank = corpus.x / (denominator[&corpus.corpus_id].sqrt() * corpus.y.sqrt());
-    }
+/// The spelling suggestions of a query word missing from the index.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Suggestion {
+    /// The analyzed query word.
+    pub term: String,
+    /// The closest indexed words, best first.
+    pub suggestions: Vec<String>,
+}
 
-    // Rank the record.
-    for record in record_ranking.iter_mut() {
-        record.rank = record.x / (denominator[&record.corpus_id].sqrt() * record.y.sqrt());
-    }
+/// A term added to the query.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Expansion {
+    /// The analyzed term.
+    pub term: String,
+    /// The query weight of the term.
+    pub weight: f64,
+    /// Where the term comes from.
+    pub source: ExpansionSource,
+}
 
-    merge(record_ranking, corpus_ranking)
+/// Where an expansion term comes from.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpansionSource {
+    /// The spelling suggestion of an unknown word.
+    Spelling,
+    /// A synonym of a query term.
+    Synonym,
+    /// The best results of the query (pseudo-relevance feedback).
+    Feedback,
 }
 
-/// Result merger.
-pub fn merge(
-    record_ranking: Vec<RecordRanking>,
-    corpus_ranking: Vec<CorpusRanking>,
-) -> Vec<SearchResult> {
-    let mut result: Vec<SearchResult> = vec![];
+/// The completions of a prefix.
+#[derive(Debug, Serialize)]
+pub struct CompletionContainer {
+    /// The requested prefix.
+    pub prefix: String,
+    /// The indexed words starting with the prefix, most frequent first.
+    pub completions: Vec<Completion>,
+}
 
-    // Rank each document separately.
-    for record in record_ranking.iter() {
-        // Get the rank of the related corpus (based on the ID).
-        let corpus_rank = corpus_ranking
-            .iter()
-            .filter(|x| x.corpus_name == record.corpus_name)
-            .map(|x| x.rank)
-            .nth(0)
-            .unwrap();
-        // Create a new result and merge it.
-        result.push(SearchResult {
-            corpus: String::from(&record.corpus_name),
-            trec_id: String::from(&record.trec_id),
-            url: String::from(&record.url),
-            record_score: record.rank,
-            corpus_score: corpus_rank,
-            score_mixed: (1_f64 - record.rank) * corpus_rank.log2(),
-        });
-    }
+/// A completion of a prefix.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Completion {
+    /// The indexed word, as most often spelled in the records.
+    pub word: String,
+    /// The total appearances of the word.
+    pub appearances: i64,
+}
 
-    // Sort descending.
-    result.sort_by(|a, b| b.score_mixed.partial_cmp(&a.score_mixed).unwrap());
+/// The WARC headers of a record.
+#[derive(Debug, Serialize)]
+pub struct RecordHeaders {
+    /// The TREC id of the record.
+    pub trec_id: String,
+    /// The WARC version of the record.
+    pub version: String,
+    /// The named header fields, in file order.
+    pub headers: Vec<HeaderField>,
+}
 
-    result
+/// A named header field.
+#[derive(Debug, Serialize)]
+pub struct HeaderField {
+    /// The field name.
+    pub name: String,
+    /// The field value.
+    pub value: String,
 }
@@ -0,0 +1,127 @@
+use crate::analysis::Token;
+use crate::configuration::SnippetSettings;
+use std::collections::HashSet;
+
+/// Build the snippet of a record.
+///
+/// The snippet is the window of words containing the most distinct query terms (then the most
+/// matches), with the matched words surrounded by the configured markers. The markers are HTML
+/// by default, so the text is HTML escaped.
+///
+/// # Arguments
+///
+/// * text      - The text of the record.
+/// * tokens    - The tokens of the text.
+/// * terms     - The query terms.
+/// * settings  - The snippet settings.
+pub fn make_snippet(
+    text: &str,
+    tokens: &[Token],
+    terms: &HashSet<&str>,
+    settings: &SnippetSettings,
+) -> String {
+    let length = settings.length.min(tokens.len());
+    if length == 0 {
+        return String::new();
+    }
+    let matched: Vec<bool> = tokens
+        .iter()
+        .map(|token| terms.contains(token.term.as_str()))
+        .collect();
+
+    // The windows around each match, leaving some context before it.
+    let mut best = (0, 0, 0);
+    for (index, _) in matched.iter().enumerate().filter(|(_, m)| **m) {
+        let start = index.saturating_sub(length / 4).min(tokens.len() - length);
+        let window = &tokens[start..start + length];
+        let distinct: HashSet<&str> = window
+            .iter()
+            .map(|token| token.term.as_str())
+            .filter(|term| terms.contains(term))
+            .collect();
+        let matches = matched[start..start + length]
+            .iter()
+            .filter(|m| **m)
+            .count();
+        if (distinct.len(), matches) > (best.0, best.1) {
+            best = (distinct.len(), matches, start);
+        }
+    }
+    let (start, end) = (best.2, best.2 + length);
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push_str("… ");
+    }
+    let mut offset = tokens[start].start;
+    for (token, matched) in tokens[start..end].iter().zip(&matched[start..end]) {
+        push_collapsed(&mut snippet, &text[offset..token.start]);
+        match matched {
+            true => {
+                snippet.push_str(&settings.pre_tag);
+                push_escaped(&mut snippet, &text[token.start..token.end]);
+                snippet.push_str(&settings.post_tag);
+            }
+            false => push_escaped(&mut snippet, &text[token.start..token.end]),
+        }
+        offset = token.end;
+    }
+    if end < tokens.len() {
+        snippet.push_str(" …");
+    }
+    snippet
+}
+
+/// Append a text between two words, collapsing its whitespace.
+///
+/// # Arguments
+///
+/// * snippet   - The snippet to append to.
+/// * text      - The text between two words.
+fn push_collapsed(snippet: &mut String, text: &str) {
+    let mut space = false;
+    for c in text.chars() {
+        match c.is_whitespace() {
+            true => space = true,
+            false => {
+                if space {
+                    snippet.push(' ');
+                    space = false;
+                }
+                push_escaped_char(snippet, c);
+            }
+        }
+    }
+    if space {
+        snippet.push(' ');
+    }
+}
+
+/// Append a text, HTML escaped.
+///
+/// # Arguments
+///
+/// * snippet   - The snippet to append to.
+/// * text      - The text.
+fn push_escaped(snippet: &mut String, text: &str) {
+    for c in text.chars() {
+        push_escaped_char(snippet, c);
+    }
+}
+
+/// Append a character, HTML escaped.
+///
+/// # Arguments
+///
+/// * snippet   - The snippet to append to.
+/// * c         - The character.
+fn push_escaped_char(snippet: &mut String, c: char) {
+    match c {
+        '&' => snippet.push_str("&amp;"),
+        '<' => snippet.push_str("&lt;"),
+        '>' => snippet.push_str("&gt;"),
+        '"' => snippet.push_str("&quot;"),
+        '\'' => snippet.push_str("&#39;"),
+        _ => snippet.push(c),
+    }
+}
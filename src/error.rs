@@ -0,0 +1,122 @@
+use crate::domain::query::QueryError;
+use crate::warc::WarcError;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use log::error;
+use std::fmt;
+
+/// The errors returned by the API.
+///
+/// Every error is returned as a JSON body with a machine-readable `code` and a `message`:
+///
+/// ```json
+/// {"code": "bad_request", "message": "The limit must be positive"}
+/// ```
+#[derive(Debug)]
+pub enum WarcseError {
+    /// The request is not valid (code `bad_request`, 400).
+    BadRequest(String),
+    /// The request matched nothing, e.g. an unknown corpus or record (code `not_found`, 404).
+    NotFound(String),
+    /// The database can not be reached (code `database_unavailable`, 503).
+    DatabaseUnavailable(sqlx::Error),
+    /// An unexpected failure, e.g. a failed query (code `internal`, 500).
+    Internal(String),
+}
+
+/// The JSON body of an error response.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ErrorBody {
+    /// The machine-readable error code.
+    pub code: String,
+    /// The description of the error.
+    pub message: String,
+}
+
+/// Implementation for `WarcseError` structure.
+impl WarcseError {
+    /// Get the machine-readable code of the error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WarcseError::BadRequest(_) => "bad_request",
+            WarcseError::NotFound(_) => "not_found",
+            WarcseError::DatabaseUnavailable(_) => "database_unavailable",
+            WarcseError::Internal(_) => "internal",
+        }
+    }
+}
+
+impl fmt::Display for WarcseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarcseError::BadRequest(message) => write!(f, "{}", message),
+            WarcseError::NotFound(message) => write!(f, "{}", message),
+            WarcseError::DatabaseUnavailable(e) => write!(f, "The database is unavailable: {}", e),
+            WarcseError::Internal(message) => write!(f, "Internal error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for WarcseError {}
+
+impl From<sqlx::Error> for WarcseError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => WarcseError::DatabaseUnavailable(e),
+            e => WarcseError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<QueryError> for WarcseError {
+    fn from(e: QueryError) -> Self {
+        WarcseError::BadRequest(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for WarcseError {
+    fn from(e: serde_json::Error) -> Self {
+        WarcseError::Internal(e.to_string())
+    }
+}
+
+impl From<WarcError> for WarcseError {
+    fn from(e: WarcError) -> Self {
+        WarcseError::Internal(e.to_string())
+    }
+}
+
+impl ResponseError for WarcseError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            WarcseError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            WarcseError::NotFound(_) => StatusCode::NOT_FOUND,
+            WarcseError::DatabaseUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            WarcseError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        // The details of the server errors are only logged.
+        let message = match self {
+            WarcseError::DatabaseUnavailable(_) => {
+                error!("{}", self);
+                String::from("The database is unavailable")
+            }
+            WarcseError::Internal(_) => {
+                error!("{}", self);
+                String::from("Internal server error")
+            }
+            _ => self.to_string(),
+        };
+
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            code: String::from(self.code()),
+            message,
+        })
+    }
+}
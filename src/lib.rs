@@ -1,5 +1,9 @@
+pub mod analysis;
 pub mod configuration;
+pub mod database;
 pub mod domain;
+pub mod error;
 pub mod routes;
 pub mod services;
 pub mod startup;
+pub mod warc;
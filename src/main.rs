@@ -1,21 +1,79 @@
+use clap::{Parser, Subcommand};
 use log::info;
 use simple_logger::SimpleLogger;
 use sqlx::PgPool;
 use std::net::TcpListener;
-use warcse::configuration::get_configuration;
+use std::path::PathBuf;
+use warcse::analysis::AnalyzerKind;
+use warcse::configuration::{get_configuration, Settings};
+use warcse::database::{check_schema, migrate};
+use warcse::services::{ingest_corpus, load_completions, remove_corpus, remove_record};
 use warcse::startup::run;
 
+/// A simple Search Engine for WARC files.
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// The available commands.
+#[derive(Subcommand)]
+enum Command {
+    /// Start the HTTP server (default).
+    Serve,
+    /// Apply the pending database migrations.
+    Migrate,
+    /// Index a list of WARC files into a corpus.
+    Ingest {
+        /// Add the files to an existing corpus, resuming any interrupted ingestion.
+        #[arg(long)]
+        incremental: bool,
+        /// The analyzer of a new corpus (defaults to the configured one).
+        #[arg(long)]
+        analyzer: Option<AnalyzerKind>,
+        /// The name of the corpus.
+        corpus: String,
+        /// The WARC files (plain or gzip compressed).
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Remove a corpus or a record from the index.
+    Delete {
+        #[command(subcommand)]
+        target: DeleteTarget,
+    },
+}
+
+/// The entities that can be deleted.
+#[derive(Subcommand)]
+enum DeleteTarget {
+    /// Remove a corpus and all of its records.
+    Corpus {
+        /// The id of the corpus.
+        id: i32,
+    },
+    /// Remove a record.
+    Record {
+        /// The TREC id of the record.
+        trec_id: String,
+    },
+}
+
 /// Application startup.
-/// Binds to the given address.
+/// Runs the requested command (by default, starts the HTTP server).
 ///
 /// # Returns
 ///
-/// * Ok    - The application is up and running.
+/// * Ok    - The command completed successfully.
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // Initialize the logger.
     SimpleLogger::new().with_utc_timestamps().init().unwrap();
 
+    let cli = Cli::parse();
+
     // Fetch the configuration.
     let configuration = get_configuration().expect("Failed to read configuration");
 
@@ -24,11 +82,91 @@ async fn main() -> std::io::Result<()> {
         .await
         .expect("Failed to connect to Postgres");
 
+    let command = cli.command.unwrap_or(Command::Serve);
+
+    // Bring the schema up to date, or refuse to work on an incompatible database.
+    if matches!(command, Command::Migrate) || configuration.database.auto_migrate {
+        migrate(&connection_pool)
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        info!("Database schema is up to date");
+    } else {
+        check_schema(&connection_pool)
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+    }
+
+    match command {
+        Command::Serve => serve(configuration, connection_pool).await,
+        Command::Migrate => Ok(()),
+        Command::Ingest {
+            incremental,
+            analyzer,
+            corpus,
+            files,
+        } => {
+            let analyzer = analyzer.unwrap_or(configuration.analysis.analyzer);
+            let summary = ingest_corpus(
+                &connection_pool,
+                &corpus,
+                &files,
+                incremental,
+                analyzer,
+                configuration.analysis.max_body_size,
+            )
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+            info!(
+                "Indexed {} records ({} skipped, {} already indexed) from {} files into corpus {}",
+                summary.records, summary.skipped, summary.unchanged, summary.files, corpus
+            );
+            Ok(())
+        }
+        Command::Delete { target } => {
+            let result = match &target {
+                DeleteTarget::Corpus { id } => remove_corpus(&connection_pool, *id).await,
+                DeleteTarget::Record { trec_id } => remove_record(&connection_pool, trec_id).await,
+            }
+            .map_err(std::io::Error::other)?;
+            match result {
+                Some(result) => info!("Deleted {} records", result.deleted_records),
+                None => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        "Nothing to delete",
+                    ))
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Bind to the configured address and serve the API.
+///
+/// # Arguments
+///
+/// * configuration     - The application settings.
+/// * connection_pool   - The PostgreSQL connection pool.
+async fn serve(configuration: Settings, connection_pool: PgPool) -> std::io::Result<()> {
     // Create the address to bind to.
     let address = format!("127.0.0.1:{}", configuration.application_port);
     let listener = TcpListener::bind(&address)?;
 
+    // The completions are kept in memory, and reloaded after each update of the index.
+    let completions = load_completions(&connection_pool, configuration.search.completion.max_limit)
+        .await
+        .map_err(|e| std::io::Error::other(e.to_string()))?;
+    info!("Loaded the completions of {} words", completions.len());
+
     info!("Binding to address: {}", address);
-    run(listener, connection_pool)?.await?;
+    run(
+        listener,
+        connection_pool,
+        configuration.search,
+        configuration.analysis,
+        completions,
+    )?
+    .await?;
     Ok(())
 }
@@ -0,0 +1,31 @@
+use crate::error::WarcseError;
+use crate::services::remove_corpus;
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+
+/// Delete a corpus along with all of its records.
+///
+/// # Arguments
+///
+/// * path  - `web::Path` the id of the corpus.
+/// * pool  - `PgPool` the PostgreSQL pool.
+///
+/// # Returns
+///
+/// * `HttpResponse`, 200 OK with the number of deleted records, or 404 if the corpus does not exist.
+pub async fn delete_corpus(
+    path: web::Path<i32>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, WarcseError> {
+    let corpus_id = path.into_inner();
+    let result = remove_corpus(pool.get_ref(), corpus_id)
+        .await?
+        .ok_or_else(|| WarcseError::NotFound(format!("Corpus {} not found", corpus_id)))?;
+
+    // Convert to JSON.
+    let result = serde_json::to_string_pretty(&result)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(result))
+}
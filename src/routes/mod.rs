@@ -1,8 +1,14 @@
 /// API description module.
 ///
 /// The presentation layer.
+mod corpus;
 mod query;
+mod record;
 mod status;
+mod suggest;
 
+pub use corpus::*;
 pub use query::*;
+pub use record::*;
 pub use status::*;
+pub use suggest::*;
@@ -1,5 +1,7 @@
-use crate::domain::ResponseContainer;
-use crate::services::perform_search;
+use crate::configuration::{FieldBoosts, RankingModel, SearchSettings};
+use crate::domain::{Cursor, Page, ResponseContainer};
+use crate::error::WarcseError;
+use crate::services::{perform_search, SearchOptions};
 use actix_web::{web, HttpResponse};
 use sqlx::PgPool;
 use std::time::Instant;
@@ -8,36 +10,108 @@ use std::time::Instant;
 #[derive(serde::Deserialize)]
 pub struct FormData {
     query: String,
+    /// The ranking model, defaults to the configured one.
+    ranking: Option<RankingModel>,
+    /// The number of results to skip.
+    #[serde(default)]
+    offset: usize,
+    /// The maximum number of results, defaults to the configured one.
+    limit: Option<usize>,
+    /// The `next_cursor` of a previous response, to fetch the page after it.
+    cursor: Option<String>,
+    /// Also search the spelling suggestions of the unknown words, defaults to the configured
+    /// behaviour.
+    expand: Option<bool>,
+    /// Expand the query with the terms of its best results, defaults to the configured behaviour.
+    feedback: Option<bool>,
+    /// The boosts of the query words found in the title or URL of a record, defaults to the
+    /// configured ones.
+    boosts: Option<FieldBoosts>,
+    /// Explain the scores of the results.
+    #[serde(default)]
+    explain: bool,
 }
 
 /// Perform a query
 ///
 /// # Arguments
 ///
-/// * form  - `web::Json` the input data. Contains the query.
-/// * pool  - `PgPool` the PostgreSQL pool.
+/// * form      - `web::Json` the input data. Contains the query.
+/// * pool      - `PgPool` the PostgreSQL pool.
+/// * settings  - `SearchSettings` the search configuration.
 pub async fn query(
     form: web::Json<FormData>,
     pool: web::Data<PgPool>,
-) -> Result<HttpResponse, actix_web::Error> {
+    settings: web::Data<SearchSettings>,
+) -> Result<HttpResponse, WarcseError> {
     // Calculate execution time.
     let start = Instant::now();
 
+    // The requested page.
+    let limit = form
+        .limit
+        .unwrap_or(settings.default_limit)
+        .min(settings.max_limit);
+    if limit == 0 {
+        return Err(WarcseError::BadRequest(String::from(
+            "The limit must be positive",
+        )));
+    }
+    let after = match &form.cursor {
+        Some(token) => Some(
+            Cursor::decode(token)
+                .ok_or_else(|| WarcseError::BadRequest(String::from("Invalid cursor")))?,
+        ),
+        None => None,
+    };
+    let page = Page {
+        offset: form.offset,
+        limit,
+        after,
+    };
+
     // Perform the search.
-    let result = perform_search(pool.get_ref(), &form.query)
-        .await
-        .map_err(|e| actix_web::error::ErrorNotFound(e))?;
+    let options = SearchOptions {
+        ranking: form.ranking.unwrap_or(settings.ranking),
+        expand: form.expand.unwrap_or(settings.spelling.expand),
+        feedback: form.feedback.unwrap_or(settings.feedback.enabled),
+        boosts: form.boosts.unwrap_or(settings.boosts),
+        explain: form.explain,
+    };
+    if [options.boosts.title, options.boosts.url]
+        .iter()
+        .any(|boost| !boost.is_finite() || *boost <= 0_f64)
+    {
+        return Err(WarcseError::BadRequest(String::from(
+            "The boosts must be positive",
+        )));
+    }
+    let result = perform_search(
+        pool.get_ref(),
+        &form.query,
+        &page,
+        &options,
+        settings.get_ref(),
+    )
+    .await?;
 
     // Get the execution time.
     let duration = start.elapsed();
 
     // Convert to JSON.
-    let result = serde_json::to_string_pretty(&ResponseContainer {
-        result_count: result.len(),
-        result,
+    let result = serde_json::to_string(&ResponseContainer {
+        result_count: result.results.len(),
+        total_hits: result.total_hits,
+        offset: page.offset,
+        limit: page.limit,
+        next_cursor: result.next_cursor.map(|c| c.encode()),
+        dropped_terms: result.dropped_terms,
+        suggestions: result.suggestions,
+        did_you_mean: result.did_you_mean,
+        expansions: result.expansions,
+        result: result.results,
         duration: duration.as_millis(),
-    })
-    .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    })?;
 
     Ok(HttpResponse::Ok()
         .content_type("application/json")
@@ -0,0 +1,124 @@
+use crate::configuration::AnalysisSettings;
+use crate::domain::{HeaderField, RecordHeaders};
+use crate::error::WarcseError;
+use crate::services::{get_archived_record, remove_record};
+use actix_web::http::header;
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+
+/// Get the page of a record, as it was captured.
+///
+/// The record is read from its WARC file, and the archived HTTP body is returned with its
+/// original content type, without its chunk framing and decompressed. The bodies whose encoding
+/// cannot be decoded, or that are larger than the configured maximum once decompressed, are
+/// returned as they are, with their `Content-Encoding`. The page is served from the origin of the
+/// API, so it is sandboxed: its scripts do not run, and it cannot use the API.
+///
+/// # Arguments
+///
+/// * path      - `web::Path` the TREC id of the record.
+/// * pool      - `PgPool` the PostgreSQL pool.
+/// * analysis  - `AnalysisSettings` the analysis configuration, with the maximum body size.
+///
+/// # Returns
+///
+/// * `HttpResponse`, 200 OK with the archived body, or 404 if the record does not exist.
+pub async fn get_record(
+    path: web::Path<String>,
+    pool: web::Data<PgPool>,
+    analysis: web::Data<AnalysisSettings>,
+) -> Result<HttpResponse, WarcseError> {
+    let record = get_archived_record(pool.get_ref(), &path.into_inner()).await?;
+
+    let mut response = HttpResponse::Ok();
+    response
+        .insert_header((header::CONTENT_SECURITY_POLICY, "sandbox"))
+        .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"));
+    let body = match record.http_payload() {
+        Some(payload) => {
+            response.content_type(
+                payload
+                    .content_type()
+                    .unwrap_or("application/octet-stream")
+                    .to_string(),
+            );
+            match payload.decoded_body(analysis.max_body_size) {
+                Ok(body) => body.into_owned(),
+                // The encodings that are not supported (e.g. `br`) and the largest bodies are
+                // left to the client.
+                Err(_) => {
+                    if let Some(encoding) = payload.header("Content-Encoding") {
+                        response.insert_header((header::CONTENT_ENCODING, encoding.to_string()));
+                    }
+                    payload.dechunked_body().into_owned()
+                }
+            }
+        }
+        None => {
+            response.content_type("application/octet-stream");
+            record.block
+        }
+    };
+
+    Ok(response.body(body))
+}
+
+/// Get the WARC headers of a record.
+///
+/// # Arguments
+///
+/// * path  - `web::Path` the TREC id of the record.
+/// * pool  - `PgPool` the PostgreSQL pool.
+///
+/// # Returns
+///
+/// * `HttpResponse`, 200 OK with the headers, or 404 if the record does not exist.
+pub async fn get_record_headers(
+    path: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, WarcseError> {
+    let trec_id = path.into_inner();
+    let record = get_archived_record(pool.get_ref(), &trec_id).await?;
+
+    // Convert to JSON.
+    let result = serde_json::to_string_pretty(&RecordHeaders {
+        trec_id,
+        version: record.version,
+        headers: record
+            .headers
+            .into_iter()
+            .map(|(name, value)| HeaderField { name, value })
+            .collect(),
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(result))
+}
+
+/// Delete a record from the index.
+///
+/// # Arguments
+///
+/// * path  - `web::Path` the TREC id of the record.
+/// * pool  - `PgPool` the PostgreSQL pool.
+///
+/// # Returns
+///
+/// * `HttpResponse`, 200 OK with the number of deleted records, or 404 if the record does not exist.
+pub async fn delete_record(
+    path: web::Path<String>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, WarcseError> {
+    let trec_id = path.into_inner();
+    let result = remove_record(pool.get_ref(), &trec_id)
+        .await?
+        .ok_or_else(|| WarcseError::NotFound(format!("Record {} not found", trec_id)))?;
+
+    // Convert to JSON.
+    let result = serde_json::to_string_pretty(&result)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(result))
+}
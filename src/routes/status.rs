@@ -1,3 +1,4 @@
+use crate::error::WarcseError;
 use crate::services::get_stats;
 use actix_web::{web, HttpRequest, HttpResponse};
 use sqlx::PgPool;
@@ -25,15 +26,12 @@ pub async fn status(_req: HttpRequest) -> HttpResponse {
 pub async fn stats(
     _req: HttpRequest,
     pool: web::Data<PgPool>,
-) -> Result<HttpResponse, actix_web::Error> {
+) -> Result<HttpResponse, WarcseError> {
     // Perform the search.
-    let result = get_stats(pool.get_ref())
-        .await
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let result = get_stats(pool.get_ref()).await?;
 
     // Convert to JSON.
-    let result = serde_json::to_string_pretty(&result)
-        .map_err(|e| actix_web::error::ErrorInternalServerError(e))?;
+    let result = serde_json::to_string_pretty(&result)?;
 
     Ok(HttpResponse::Ok()
         .content_type("application/json")
@@ -0,0 +1,53 @@
+use crate::configuration::SearchSettings;
+use crate::domain::{CompletionContainer, Completions};
+use crate::error::WarcseError;
+use actix_web::{web, HttpResponse};
+use std::sync::RwLock;
+
+/// Completion query.
+#[derive(serde::Deserialize)]
+pub struct SuggestQuery {
+    /// The start of the word being typed.
+    prefix: String,
+    /// The maximum number of completions, defaults to the configured one.
+    limit: Option<usize>,
+}
+
+/// Complete a prefix with the most frequent indexed words.
+///
+/// The completions are served from memory, and reloaded after each update of the index.
+///
+/// # Arguments
+///
+/// * query         - `web::Query` the prefix and limit.
+/// * completions   - `Completions` the completions of the indexed words.
+/// * settings      - `SearchSettings` the search configuration.
+///
+/// # Returns
+///
+/// * `HttpResponse`, 200 OK with the completions, most frequent first.
+pub async fn suggest(
+    query: web::Query<SuggestQuery>,
+    completions: web::Data<RwLock<Completions>>,
+    settings: web::Data<SearchSettings>,
+) -> Result<HttpResponse, WarcseError> {
+    let limit = query
+        .limit
+        .unwrap_or(settings.completion.default_limit)
+        .min(settings.completion.max_limit);
+
+    let completions = completions
+        .read()
+        .map_err(|e| WarcseError::Internal(e.to_string()))?
+        .complete(&query.prefix, limit);
+
+    // Convert to JSON.
+    let result = serde_json::to_string(&CompletionContainer {
+        prefix: query.into_inner().prefix,
+        completions,
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(result))
+}
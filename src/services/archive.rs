@@ -0,0 +1,49 @@
+use crate::error::WarcseError;
+use crate::warc::{RecordOffset, WarcReader, WarcRecord};
+use sqlx::PgPool;
+
+/// Read a record from the WARC file it was indexed from.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+/// * trec_id   - The TREC id of the record.
+///
+/// # Returns
+///
+/// * `WarcseError::NotFound` if the record does not exist, or was indexed without its offset.
+pub async fn get_archived_record(pool: &PgPool, trec_id: &str) -> Result<WarcRecord, WarcseError> {
+    let location = sqlx::query!(
+        r#"
+        SELECT cf.name, ri.file_offset, ri.member_offset
+        FROM record_index ri
+                 JOIN corpus_files cf ON cf.id = ri.file_id
+        WHERE ri.trec_id = $1
+        ORDER BY ri.id
+        LIMIT 1
+        "#,
+        trec_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| WarcseError::NotFound(format!("Record {} not found", trec_id)))?;
+
+    let offset = match (location.file_offset, location.member_offset) {
+        (Some(file), Some(member)) => RecordOffset {
+            file: file as u64,
+            member: member as u64,
+        },
+        _ => {
+            return Err(WarcseError::NotFound(format!(
+                "Record {} was indexed without its offset",
+                trec_id
+            )))
+        }
+    };
+
+    // The file is read outside of the async runtime.
+    tokio::task::spawn_blocking(move || WarcReader::read_at(location.name, offset))
+        .await
+        .map_err(|e| WarcseError::Internal(e.to_string()))?
+        .map_err(WarcseError::from)
+}
@@ -0,0 +1,121 @@
+use crate::domain::Completions;
+use log::{error, info};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// The channel notified when `word_index` is updated.
+pub const WORD_INDEX_CHANNEL: &str = "word_index_updated";
+
+/// Load the completions of the indexed words.
+///
+/// The words are completed with their most frequent spelling in the records, rather than their
+/// analyzed (e.g. stemmed) form, and the words sharing a spelling are merged.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+/// * depth     - The number of completions kept for each prefix.
+pub async fn load_completions(pool: &PgPool, depth: usize) -> Result<Completions, sqlx::Error> {
+    let words = sqlx::query!(
+        r#"
+        SELECT COALESCE(wi.surface, wi.word) AS "word!",
+               SUM(wi.total_appearances)::BIGINT AS "total_appearances!"
+        FROM word_index wi
+        WHERE wi.total_appearances > 0
+        GROUP BY COALESCE(wi.surface, wi.word)
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Completions::new(
+        words
+            .into_iter()
+            .map(|w| (w.word, w.total_appearances))
+            .collect(),
+        depth,
+    ))
+}
+
+/// The delay before listening again for the index updates, doubled after each failure.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+/// The longest delay before listening again.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Reload the completions each time the index is updated.
+///
+/// Listens on `WORD_INDEX_CHANNEL`, which is notified when the statistics of the index are
+/// updated (after an ingestion or a deletion), possibly by another process. When the listener
+/// fails, it is connected again after a delay growing up to `MAX_RECONNECT_DELAY`, and the
+/// completions reloaded for the updates missed in between.
+///
+/// # Arguments
+///
+/// * pool          - The PostgreSQL connection pool.
+/// * completions   - The completions to replace.
+/// * depth         - The number of completions kept for each prefix.
+pub async fn refresh_completions(
+    pool: PgPool,
+    completions: Arc<RwLock<Completions>>,
+    depth: usize,
+) {
+    let mut delay = RECONNECT_DELAY;
+    let mut reconnected = false;
+    loop {
+        match listen(&pool).await {
+            Ok(mut listener) => {
+                if reconnected {
+                    reload_completions(&pool, &completions, depth).await;
+                }
+                loop {
+                    if let Err(e) = listener.recv().await {
+                        error!("Failed to receive the index updates: {}", e);
+                        break;
+                    }
+                    delay = RECONNECT_DELAY;
+                    reload_completions(&pool, &completions, depth).await;
+                }
+            }
+            Err(e) => error!("Failed to listen for index updates: {}", e),
+        }
+
+        info!("Listening again for index updates in {:?}", delay);
+        sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        reconnected = true;
+    }
+}
+
+/// Connect a listener to `WORD_INDEX_CHANNEL`.
+///
+/// # Arguments
+///
+/// * pool  - The PostgreSQL connection pool.
+async fn listen(pool: &PgPool) -> Result<PgListener, sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(WORD_INDEX_CHANNEL).await?;
+    Ok(listener)
+}
+
+/// Load the completions again, and replace the served ones.
+///
+/// # Arguments
+///
+/// * pool          - The PostgreSQL connection pool.
+/// * completions   - The completions to replace.
+/// * depth         - The number of completions kept for each prefix.
+async fn reload_completions(pool: &PgPool, completions: &RwLock<Completions>, depth: usize) {
+    match load_completions(pool, depth).await {
+        Ok(loaded) => {
+            info!("Reloaded the completions of {} words", loaded.len());
+            // The completions are replaced whole, so a poisoned lock is recovered instead of
+            // panicking, and the suggestions served again.
+            *completions.write().unwrap_or_else(PoisonError::into_inner) = loaded;
+            completions.clear_poison();
+        }
+        Err(e) => error!("Failed to reload the completions: {}", e),
+    }
+}
@@ -0,0 +1,197 @@
+use crate::services::{discount_records, lock_statistics, update_statistics};
+use sqlx::PgPool;
+
+/// The outcome of a deletion.
+#[derive(Debug, serde::Serialize)]
+pub struct DeleteResponse {
+    /// The number of records removed.
+    pub deleted_records: u64,
+}
+
+/// Remove a corpus, its files and all of its records from the index.
+///
+/// The records are taken out of the index statistics, then the rows are removed and the
+/// statistics updated in a single transaction.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+/// * corpus_id - The id of the corpus in `corpus_info`.
+///
+/// # Returns
+///
+/// * `Some(DeleteResponse)` if the corpus was removed, `None` if it does not exist.
+pub async fn remove_corpus(
+    pool: &PgPool,
+    corpus_id: i32,
+) -> Result<Option<DeleteResponse>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    lock_statistics(&mut tx).await?;
+
+    let exists = sqlx::query!(
+        r#"SELECT EXISTS(SELECT * FROM corpus_info WHERE id = $1 FOR UPDATE) AS "exists!""#,
+        corpus_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if !exists.exists {
+        return Ok(None);
+    }
+
+    let records: Vec<i32> = sqlx::query!(
+        r#"SELECT id FROM record_index WHERE corpus_id = $1"#,
+        corpus_id
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|row| row.id)
+    .collect();
+    discount_records(&mut tx, &records).await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM word_record_index
+        WHERE record IN (SELECT id FROM record_index WHERE corpus_id = $1)
+        "#,
+        corpus_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM record_meta
+        WHERE record IN (SELECT id FROM record_index WHERE corpus_id = $1)
+        "#,
+        corpus_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM record_text
+        WHERE record IN (SELECT id FROM record_index WHERE corpus_id = $1)
+        "#,
+        corpus_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM record_field_index
+        WHERE record IN (SELECT id FROM record_index WHERE corpus_id = $1)
+        "#,
+        corpus_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    let deleted_records = sqlx::query!(
+        r#"DELETE FROM record_index WHERE corpus_id = $1"#,
+        corpus_id
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // The corpus is left without words by the update, and removed once it is no longer counted.
+    update_statistics(&mut tx).await?;
+    sqlx::query!(
+        r#"DELETE FROM corpus_files WHERE corpus_id = $1"#,
+        corpus_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM word_corpus_index WHERE corpus = $1"#,
+        corpus_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(r#"DELETE FROM corpus_info WHERE id = $1"#, corpus_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(Some(DeleteResponse { deleted_records }))
+}
+
+/// Remove a record from the index.
+///
+/// All the records with the given TREC id are taken out of the index statistics, removed, and the
+/// statistics updated in a single transaction.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+/// * trec_id   - The TREC id of the record.
+///
+/// # Returns
+///
+/// * `Some(DeleteResponse)` if the record was removed, `None` if it does not exist.
+pub async fn remove_record(
+    pool: &PgPool,
+    trec_id: &str,
+) -> Result<Option<DeleteResponse>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    lock_statistics(&mut tx).await?;
+
+    let records: Vec<i32> = sqlx::query!(
+        r#"SELECT id FROM record_index WHERE trec_id = $1 FOR UPDATE"#,
+        trec_id
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|row| row.id)
+    .collect();
+    if records.is_empty() {
+        return Ok(None);
+    }
+    discount_records(&mut tx, &records).await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM word_record_index
+        WHERE record IN (SELECT id FROM record_index WHERE trec_id = $1)
+        "#,
+        trec_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM record_meta
+        WHERE record IN (SELECT id FROM record_index WHERE trec_id = $1)
+        "#,
+        trec_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM record_text
+        WHERE record IN (SELECT id FROM record_index WHERE trec_id = $1)
+        "#,
+        trec_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM record_field_index
+        WHERE record IN (SELECT id FROM record_index WHERE trec_id = $1)
+        "#,
+        trec_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    let deleted_records = sqlx::query!(r#"DELETE FROM record_index WHERE trec_id = $1"#, trec_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+    update_statistics(&mut tx).await?;
+    tx.commit().await?;
+
+    Ok(Some(DeleteResponse { deleted_records }))
+}
@@ -0,0 +1,134 @@
+use crate::configuration::FeedbackSettings;
+use crate::domain::{Expansion, ExpansionSource, Hit};
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+/// The expansions of a query.
+#[derive(Debug, Default)]
+pub struct QueryExpansion {
+    /// The alternatives of each query term, searched along with it.
+    pub alternatives: HashMap<String, Vec<String>>,
+    /// The added terms, with their query weight.
+    pub expansions: Vec<Expansion>,
+}
+
+/// Implementation for `QueryExpansion` structure.
+impl QueryExpansion {
+    /// Add alternatives of a query term.
+    ///
+    /// The words already in the query, or already added, are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * term      - The query term.
+    /// * words     - The alternatives of the term.
+    /// * weight    - The query weight of the alternatives.
+    /// * source    - Where the alternatives come from.
+    /// * query     - The terms of the query.
+    pub fn add(
+        &mut self,
+        term: &str,
+        words: &[String],
+        weight: f64,
+        source: ExpansionSource,
+        query: &[String],
+    ) {
+        for word in words {
+            if query.contains(word) || self.expansions.iter().any(|e| &e.term == word) {
+                continue;
+            }
+            self.alternatives
+                .entry(term.to_string())
+                .or_default()
+                .push(word.clone());
+            self.expansions.push(Expansion {
+                term: word.clone(),
+                weight,
+                source,
+            });
+        }
+    }
+
+    /// Get the query weight of a term, 1 for the terms of the query.
+    ///
+    /// # Arguments
+    ///
+    /// * term      - The term.
+    pub fn weight(&self, term: &str) -> f64 {
+        self.expansions
+            .iter()
+            .find(|e| e.term == term)
+            .map_or(1_f64, |e| e.weight)
+    }
+}
+
+/// Find the terms to add to a query, from its best results (Rocchio pseudo-relevance feedback).
+///
+/// The best results are assumed to be relevant, and their centroid, the mean TF-IDF of each term
+/// over them, is added to the query. The terms with the highest mean are kept, weighted in
+/// proportion to it, the best one having the configured weight. The stopwords are never added.
+///
+/// # Arguments
+///
+/// * hits      - The scored records of the query.
+/// * exclude   - The terms not to add (the query terms, the listed stopwords).
+/// * threshold - The fraction of the records a stopword appears in.
+/// * settings  - The feedback settings.
+/// * pool      - The PostgreSQL connection pool.
+pub async fn get_feedback_terms(
+    hits: &[Hit],
+    exclude: &[String],
+    threshold: f64,
+    settings: &FeedbackSettings,
+    pool: &PgPool,
+) -> Result<Vec<Expansion>, sqlx::Error> {
+    let mut best: Vec<&Hit> = hits.iter().collect();
+    best.sort_unstable_by(|a, b| {
+        b.score_mixed
+            .total_cmp(&a.score_mixed)
+            .then_with(|| a.record.cmp(&b.record))
+    });
+    let records: Vec<i32> = best
+        .into_iter()
+        .take(settings.documents)
+        .map(|hit| hit.record)
+        .collect();
+    if records.is_empty() || settings.terms == 0 {
+        return Ok(vec![]);
+    }
+
+    let terms = sqlx::query!(
+        r#"
+        SELECT wri.word                                          AS word,
+               SUM(wri.tf * wri.idf) / CARDINALITY($1::INT[])   AS "centroid!"
+        FROM word_record_index wri
+                 JOIN word_index wi
+                      ON wi.word = wri.word
+        WHERE wri.record = ANY($1)
+          AND wri.word <> ALL($2)
+          AND wi.frequency <= $3
+        GROUP BY wri.word
+        ORDER BY 2 DESC, wri.word
+        LIMIT $4
+        "#,
+        &records,
+        exclude,
+        threshold,
+        settings.terms as i64
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let max = terms.first().map_or(0_f64, |t| t.centroid);
+    if max <= 0_f64 {
+        return Ok(vec![]);
+    }
+    Ok(terms
+        .into_iter()
+        .map(|t| Expansion {
+            term: t.word,
+            weight: settings.weight * t.centroid / max,
+            source: ExpansionSource::Feedback,
+        })
+        .collect())
+}
@@ -0,0 +1,475 @@
+use crate::analysis::{extract_document, get_analyzer, Analyzer, AnalyzerKind};
+use crate::services::update_statistics;
+use crate::warc::{WarcError, WarcReader, WarcRecord};
+use log::{info, warn};
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Errors raised while ingesting WARC files.
+#[derive(Debug)]
+pub enum IngestError {
+    /// The database returned an error.
+    Database(sqlx::Error),
+    /// A WARC file could not be read.
+    Warc(PathBuf, WarcError),
+    /// A corpus with the given name already exists.
+    CorpusExists(String),
+    /// The file has already been added to a corpus.
+    FileExists(PathBuf),
+    /// The file belongs to a different corpus.
+    FileInOtherCorpus(PathBuf),
+    /// The corpus was created with an unknown analyzer.
+    UnknownAnalyzer(String),
+}
+
+impl fmt::Display for IngestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IngestError::Database(e) => write!(f, "Database error: {}", e),
+            IngestError::Warc(path, e) => write!(f, "Failed to read {}: {}", path.display(), e),
+            IngestError::CorpusExists(name) => write!(f, "Corpus {} already exists", name),
+            IngestError::FileExists(path) => {
+                write!(f, "File {} is already indexed", path.display())
+            }
+            IngestError::FileInOtherCorpus(path) => {
+                write!(f, "File {} belongs to another corpus", path.display())
+            }
+            IngestError::UnknownAnalyzer(name) => write!(f, "Unknown analyzer {}", name),
+        }
+    }
+}
+
+impl std::error::Error for IngestError {}
+
+impl From<sqlx::Error> for IngestError {
+    fn from(e: sqlx::Error) -> Self {
+        IngestError::Database(e)
+    }
+}
+
+/// Summary of an ingestion run.
+#[derive(Debug, Default)]
+pub struct IngestSummary {
+    /// The id of the corpus in `corpus_info`.
+    pub corpus_id: i32,
+    /// The number of files read.
+    pub files: usize,
+    /// The number of records indexed.
+    pub records: usize,
+    /// The number of records skipped (non-response records).
+    pub skipped: usize,
+    /// The number of records left untouched, since they were indexed by a previous run.
+    pub unchanged: usize,
+}
+
+/// Index the given WARC files into a corpus.
+///
+/// Every `response` record is analyzed and added to `record_index`, `word_index` and
+/// `word_record_index`. Once all the files are read, the global statistics (`tf`, `idf`,
+/// `frequency` etc.) are updated with the postings of the new records, which are only searched
+/// from then on.
+///
+/// In incremental mode the corpus may already exist: new files are added to it, and files that
+/// are already registered are resumed, skipping the records flagged as `analyzed`. The records
+/// added to an existing corpus are analyzed with the analyzer the corpus was created with.
+///
+/// # Arguments
+///
+/// * pool          - The PostgreSQL connection pool.
+/// * corpus        - The name of the corpus.
+/// * files         - The WARC files to index.
+/// * incremental   - Add to (or resume) an existing corpus instead of creating a new one.
+/// * analyzer      - The analyzer of the corpus, if it is created.
+/// * max_body_size - The maximum size of a decompressed body, larger ones are not indexed.
+pub async fn ingest_corpus(
+    pool: &PgPool,
+    corpus: &str,
+    files: &[PathBuf],
+    incremental: bool,
+    analyzer: AnalyzerKind,
+    max_body_size: usize,
+) -> Result<IngestSummary, IngestError> {
+    let existing = sqlx::query!(
+        r#"SELECT id, analyzer FROM corpus_info WHERE name = $1"#,
+        corpus
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let (corpus_id, analyzer) = match existing {
+        Some(row) if incremental => {
+            let recorded = row
+                .analyzer
+                .parse::<AnalyzerKind>()
+                .map_err(|_| IngestError::UnknownAnalyzer(row.analyzer))?;
+            if recorded != analyzer {
+                warn!(
+                    "Corpus {} is analyzed with the {} analyzer, not {}",
+                    corpus, recorded, analyzer
+                );
+            }
+            (row.id, recorded)
+        }
+        Some(_) => return Err(IngestError::CorpusExists(String::from(corpus))),
+        None => {
+            let id = sqlx::query!(
+                r#"INSERT INTO corpus_info (name, analyzer) VALUES ($1, $2) RETURNING id"#,
+                corpus,
+                analyzer.name()
+            )
+            .fetch_one(pool)
+            .await?
+            .id;
+            (id, analyzer)
+        }
+    };
+    let analyzer = get_analyzer(analyzer);
+
+    let mut summary = IngestSummary {
+        corpus_id,
+        ..Default::default()
+    };
+    for file in files {
+        ingest_file(
+            pool,
+            corpus_id,
+            file,
+            incremental,
+            analyzer.as_ref(),
+            max_body_size,
+            &mut summary,
+        )
+        .await?;
+    }
+
+    info!("Updating the index statistics");
+    let mut tx = pool.begin().await?;
+    update_statistics(&mut tx).await?;
+    tx.commit().await?;
+
+    Ok(summary)
+}
+
+/// Register a file in `corpus_files` and index all of its records.
+///
+/// If the file is already registered (incremental mode only), the records that were not fully
+/// analyzed by the previous run are removed and indexed again, while the analyzed ones are kept.
+///
+/// # Arguments
+///
+/// * pool          - The PostgreSQL connection pool.
+/// * corpus_id     - The corpus the file belongs to.
+/// * path          - The path of the WARC file.
+/// * incremental   - Resume the file if it is already registered.
+/// * analyzer      - The analyzer of the corpus.
+/// * max_body_size - The maximum size of a decompressed body.
+/// * summary       - The summary to update.
+async fn ingest_file(
+    pool: &PgPool,
+    corpus_id: i32,
+    path: &Path,
+    incremental: bool,
+    analyzer: &dyn Analyzer,
+    max_body_size: usize,
+    summary: &mut IngestSummary,
+) -> Result<(), IngestError> {
+    let warc_error = |e| IngestError::Warc(path.to_path_buf(), e);
+    let name = path
+        .canonicalize()
+        .map_err(|e| warc_error(WarcError::Io(e)))?;
+    let reader = WarcReader::open(&name).map_err(warc_error)?;
+
+    let file_name = name.to_string_lossy();
+    let existing = sqlx::query!(
+        r#"SELECT id, corpus_id FROM corpus_files WHERE name = $1"#,
+        file_name.as_ref()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let file_id = match existing {
+        Some(row) if row.corpus_id != corpus_id => {
+            return Err(IngestError::FileInOtherCorpus(name))
+        }
+        Some(row) if incremental => {
+            info!("Resuming {}", file_name);
+            remove_unanalyzed_records(pool, row.id).await?;
+            row.id
+        }
+        Some(_) => return Err(IngestError::FileExists(name)),
+        None => {
+            info!("Indexing {}", file_name);
+            sqlx::query!(
+                r#"INSERT INTO corpus_files (name, corpus_id) VALUES ($1, $2) RETURNING id"#,
+                file_name.as_ref(),
+                corpus_id
+            )
+            .fetch_one(pool)
+            .await?
+            .id
+        }
+    };
+
+    // The records completed by a previous run.
+    let analyzed: HashSet<String> = sqlx::query!(
+        r#"SELECT trec_id FROM record_index WHERE file_id = $1 AND analyzed"#,
+        file_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|row| row.trec_id)
+    .collect();
+
+    for record in reader {
+        let record = record.map_err(warc_error)?;
+        if record.warc_type() != Some("response") {
+            summary.skipped += 1;
+            continue;
+        }
+        let trec_id = match record.trec_id().or_else(|| record.record_id()) {
+            Some(id) => id,
+            None => {
+                warn!("Skipping response record without an id");
+                summary.skipped += 1;
+                continue;
+            }
+        };
+        if analyzed.contains(trec_id) {
+            summary.unchanged += 1;
+            continue;
+        }
+        index_record(
+            pool,
+            corpus_id,
+            file_id,
+            trec_id,
+            &record,
+            analyzer,
+            max_body_size,
+        )
+        .await?;
+        summary.records += 1;
+    }
+    summary.files += 1;
+
+    Ok(())
+}
+
+/// Remove the records of a file that were not completely analyzed, along with their postings.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+/// * file_id   - The id of the file in `corpus_files`.
+async fn remove_unanalyzed_records(pool: &PgPool, file_id: i32) -> Result<(), IngestError> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM word_record_index
+        WHERE record IN (SELECT id FROM record_index WHERE file_id = $1 AND NOT analyzed)
+        "#,
+        file_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM record_meta
+        WHERE record IN (SELECT id FROM record_index WHERE file_id = $1 AND NOT analyzed)
+        "#,
+        file_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM record_text
+        WHERE record IN (SELECT id FROM record_index WHERE file_id = $1 AND NOT analyzed)
+        "#,
+        file_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"
+        DELETE FROM record_field_index
+        WHERE record IN (SELECT id FROM record_index WHERE file_id = $1 AND NOT analyzed)
+        "#,
+        file_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM record_index WHERE file_id = $1 AND NOT analyzed"#,
+        file_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Analyze a record and store it in the index.
+///
+/// The main text of the record is indexed, while the title and the description of HTML pages are
+/// stored in `record_meta`, and the words of the title and URL in `record_field_index`.
+///
+/// The record is first registered on its own, unanalyzed. Its postings are then written in a
+/// single transaction that flags it as `analyzed`, so a record interrupted in between is left
+/// unanalyzed, and indexed again when its file is resumed.
+///
+/// # Arguments
+///
+/// * pool          - The PostgreSQL connection pool.
+/// * corpus_id     - The corpus the record belongs to.
+/// * file_id       - The file the record was read from.
+/// * trec_id       - The id of the record.
+/// * record        - The WARC record.
+/// * analyzer      - The analyzer of the corpus.
+/// * max_body_size - The maximum size of a decompressed body.
+async fn index_record(
+    pool: &PgPool,
+    corpus_id: i32,
+    file_id: i32,
+    trec_id: &str,
+    record: &WarcRecord,
+    analyzer: &dyn Analyzer,
+    max_body_size: usize,
+) -> Result<(), IngestError> {
+    // PostgreSQL does not store NUL characters.
+    let document = extract_document(record, max_body_size);
+    let text = document.text.replace('\0', "");
+    let (tokens, surfaces): (Vec<String>, Vec<String>) = analyzer
+        .tokens(&text)
+        .into_iter()
+        .map(|token| (token.term, text[token.start..token.end].to_lowercase()))
+        .unzip();
+    let total_words = tokens.len() as i32;
+    let words: Vec<&str> = tokens
+        .iter()
+        .map(String::as_str)
+        .collect::<HashSet<&str>>()
+        .into_iter()
+        .collect();
+
+    let record_id = sqlx::query!(
+        r#"
+        INSERT INTO record_index (trec_id, uri, version, analyzed, total_words, corpus_id, file_id,
+                                  file_offset, member_offset)
+        VALUES ($1, $2, $3, FALSE, $4, $5, $6, $7, $8)
+        RETURNING id
+        "#,
+        trec_id,
+        record.target_uri().unwrap_or_default(),
+        &record.version,
+        total_words,
+        corpus_id,
+        file_id,
+        record.offset.file as i64,
+        record.offset.member as i64
+    )
+    .fetch_one(pool)
+    .await?
+    .id;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query!(
+        r#"
+        INSERT INTO word_index (word)
+        SELECT * FROM UNNEST($1::TEXT[])
+        ON CONFLICT (word) DO NOTHING
+        "#,
+        &words as &[&str]
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // The title and URL are analyzed like the text, for the field boosts. The URL is split on
+    // its punctuation, which the word boundaries keep inside the host names.
+    let uri: String = record
+        .target_uri()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    let fields = [
+        (
+            "title",
+            analyzer.analyze(document.title.as_deref().unwrap_or_default()),
+        ),
+        ("url", analyzer.analyze(&uri)),
+    ];
+    for (field, words) in fields {
+        sqlx::query!(
+            r#"
+            INSERT INTO record_field_index (word, record, field)
+            SELECT DISTINCT w.word, $2::INT, $3::TEXT
+            FROM UNNEST($1::TEXT[]) AS w (word)
+            "#,
+            &words,
+            record_id,
+            field
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let meta = [
+        ("title", document.title),
+        ("description", document.description),
+    ];
+    for (name, value) in meta {
+        if let Some(value) = value {
+            sqlx::query!(
+                r#"INSERT INTO record_meta (record, name, meta) VALUES ($1, $2, $3)"#,
+                record_id,
+                name,
+                value.replace('\0', "")
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    sqlx::query!(
+        r#"INSERT INTO record_text (record, text) VALUES ($1, $2)"#,
+        record_id,
+        text
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // The tokens are grouped by word, keeping their positions and their most frequent spelling.
+    sqlx::query!(
+        r#"
+        INSERT INTO word_record_index (word, record, appearances, tf, positions, surface)
+        SELECT t.word,
+               $2,
+               COUNT(*),
+               COUNT(*)::DOUBLE PRECISION / $3,
+               ARRAY_AGG((t.position - 1)::INT ORDER BY t.position),
+               MODE() WITHIN GROUP (ORDER BY t.surface)
+        FROM UNNEST($1::TEXT[], $4::TEXT[]) WITH ORDINALITY AS t (word, surface, position)
+        GROUP BY t.word
+        "#,
+        &tokens,
+        record_id,
+        total_words.max(1) as f64,
+        &surfaces
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"UPDATE record_index SET analyzed = TRUE WHERE id = $1"#,
+        record_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
@@ -1,6 +1,18 @@
 /// Business layer.
+mod archive;
+mod completion;
+mod delete;
+mod expansion;
+mod ingest;
 mod search;
+mod spelling;
 pub(crate) mod stats;
 
+pub use archive::*;
+pub use completion::*;
+pub use delete::*;
+pub use expansion::*;
+pub use ingest::*;
 pub use search::*;
+pub use spelling::*;
 pub use stats::*;
@@ -1,32 +1,43 @@
-use crate::domain::{build_result, SearchResult};
-use convert_case::{Case, Casing};
-use log::{error, warn};
-use sqlx::PgPool;
-use std::collections::HashMap;
-
-/// The query record information.
-pub struct QueryWordRecordInfo {
+use crate::analysis::{get_analyzer, Analyzer, AnalyzerKind};
+use crate::configuration::{FieldBoosts, RankingModel, SearchSettings, StopwordMode};
+use crate::domain::query::{parse_query, Expr, Field};
+use crate::domain::ranking::{get_ranker, Proximity};
+use crate::domain::{
+    make_snippet, select_page, ExpansionSource, Explanation, Hit, Page, RankedPage, RecordInfo,
+    SearchPage,
+};
+use crate::error::WarcseError;
+use crate::services::{did_you_mean, get_feedback_terms, get_suggestions, QueryExpansion};
+use futures_util::TryStreamExt;
+use log::warn;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use std::collections::{HashMap, HashSet};
+
+/// A posting of a query word, as streamed from the index.
+#[derive(sqlx::FromRow)]
+pub struct Posting {
+    // The position of the word in the query.
+    pub word: i32,
     // The record's id.
-    pub record_id: i32,
-    // The word.
-    pub word: String,
-    // The trec id of the record.
-    pub trec_id: String,
-    // The linked corpus name.
-    pub corpus_name: String,
+    pub record: i32,
     // The corpus id.
     pub corpus_id: i32,
     // The tf_idf of the word.
-    pub tf_idf: Option<f64>,
-    // The linked URL.
-    pub url: String,
+    pub tf_idf: f64,
+    // The stored tf of the word.
+    pub tf: f64,
+    // The stored idf of the word.
+    pub idf: f64,
     // The appearances of the word in the record.
-    pub appearances_r: i32,
+    pub appearances: i32,
     // Total words in the record.
-    pub total_words_r: Option<i32>,
+    pub total_words: i32,
+    // The positions of the word in the record (only loaded for the proximity boost).
+    pub positions: Vec<i32>,
 }
 
 /// The query word corpus information.
+#[derive(Clone)]
 pub struct QueryWordCorpusInfo {
     // The corpus id.
     pub corpus_id: i32,
@@ -42,6 +53,33 @@ pub struct QueryWordCorpusInfo {
     pub tf_idf: Option<f64>,
 }
 
+/// Statistics over all the counted records.
+pub struct CollectionStats {
+    // The number of records.
+    pub total_records: i64,
+    // The average record length (in words).
+    pub average_length: f64,
+}
+
+/// Get the statistics over all the counted records.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+pub async fn get_collection_stats(pool: &PgPool) -> Result<CollectionStats, sqlx::Error> {
+    sqlx::query_as!(
+        CollectionStats,
+        r#"
+        SELECT COUNT(*)                                         AS "total_records!",
+               COALESCE(AVG(total_words), 0)::DOUBLE PRECISION AS "average_length!"
+        FROM record_index
+        WHERE counted
+        "#
+    )
+    .fetch_one(pool)
+    .await
+}
+
 /// A storage place for a query word.
 pub struct QueryWord {
     // The query word.
@@ -50,8 +88,12 @@ pub struct QueryWord {
     pub tfidf: HashMap<i32, f64>,
     // The vector if TF-IDF compared to the corpus.
     pub word_corpus_tf_idf: Vec<QueryWordCorpusInfo>,
-    // The vector of TF-IDF compared to the records.
-    pub word_record_tf_idf: Vec<QueryWordRecordInfo>,
+    // The number of records containing the word.
+    pub records: i64,
+    // The weight of the word in the query (its boost, lower for the expanded words).
+    pub weight: f64,
+    // The boost of the records whose title or URL contain the word.
+    pub record_boosts: HashMap<i32, f64>,
 }
 
 /// Implementation for `QueryWord` structure.
@@ -63,236 +105,909 @@ impl QueryWord {
     /// * word      - The word to lik it to.
     pub fn new(word: String) -> QueryWord {
         QueryWord {
-            word: String::from(word),
+            word,
             tfidf: HashMap::new(),
             word_corpus_tf_idf: vec![],
-            word_record_tf_idf: vec![],
+            records: 0,
+            weight: 1_f64,
+            record_boosts: HashMap::new(),
         }
     }
 
-    /// Create the query word TF-IDF for each corpus that contains it.
+    /// Get the boost of a record, 1 unless the word is in its title or URL.
     ///
     /// # Arguments
     ///
-    /// * pool      - The PostgreSQL connection pool.
-    pub async fn get_corpus_tf_idf(&mut self, pool: &PgPool) -> Result<(), sqlx::Error> {
-        let result: Vec<QueryWordCorpusInfo> = sqlx::query_as!(
-            QueryWordCorpusInfo,
-            r#"
-            SELECT ci.id                            AS corpus_id,
-                   ci.name                          AS corpus_name,
-                   wci.word                         AS word,
-                   wci.appearances                  AS appearances_c,
-                   (SELECT SUM(wcii.appearances)
-                    FROM word_corpus_index wcii
-                    WHERE wcii.corpus = wci.corpus) AS total_words_c,
-                   wci.tf * wci.idf                 AS tf_idf
-            FROM word_corpus_index wci
-                     JOIN corpus_info ci
-                          ON wci.corpus = ci.id
-            WHERE wci.word = $1
+    /// * record    - The record id.
+    pub fn record_boost(&self, record: i32) -> f64 {
+        self.record_boosts.get(&record).copied().unwrap_or(1_f64)
+    }
+}
+
+/// Calculate the query weight of the query words, for each corpus that contains them.
+///
+/// The weight of a word is its IDF over the records of the corpus, divided by the query length and
+/// multiplied by the weight of the word in the query.
+/// The number of records containing each word is also set.
+///
+/// # Arguments
+///
+/// * query_words   - The query words.
+/// * pool          - The PostgreSQL connection pool.
+pub async fn calculate_query_weights(
+    query_words: &mut [QueryWord],
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    let words: Vec<&str> = query_words.iter().map(|w| w.word.as_str()).collect();
+    let result = sqlx::query!(
+        r#"
+        SELECT wri.word                                                  AS word,
+               ri.corpus_id                                              AS corpus_id,
+               COUNT(*)                                                  AS "records!",
+               (1 + log((SELECT COUNT(*) FROM record_index fi) / COUNT(*))) AS idf
+        FROM word_record_index wri
+                 JOIN record_index ri
+                      ON wri.record = ri.id
+        WHERE wri.word = ANY($1)
+        GROUP BY wri.word, ri.corpus_id
         "#,
-            &self.word
-        )
-        .fetch_all(pool)
-        .await?;
+        &words as &[&str]
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let total_words = query_words.len() as f64;
+    for row in result.iter() {
+        for word in query_words.iter_mut().filter(|w| w.word == row.word) {
+            word.tfidf.insert(
+                row.corpus_id,
+                (word.weight / total_words) * row.idf.unwrap_or_default(),
+            );
+            word.records += row.records;
+        }
+    }
 
-        self.word_corpus_tf_idf = result;
+    Ok(())
+}
 
-        Ok(())
+/// Get the boost of the records whose title or URL contain the query words.
+///
+/// The title is the `title` metadata of the record. The words of both are analyzed by the
+/// indexer with the analyzer of the corpus, and stored in `record_field_index`, so that the
+/// query words are compared with whole analyzed words, like in the text.
+///
+/// # Arguments
+///
+/// * query_words   - The query words.
+/// * boosts        - The boosts of the fields.
+/// * pool          - The PostgreSQL connection pool.
+pub async fn get_record_boosts(
+    query_words: &mut [QueryWord],
+    boosts: &FieldBoosts,
+    pool: &PgPool,
+) -> Result<(), sqlx::Error> {
+    if boosts.title == 1_f64 && boosts.url == 1_f64 {
+        return Ok(());
     }
 
-    /// Get the per-corpus TF-IDF for the word.
-    ///
-    /// # Arguments
-    ///
-    /// * pool      - The PostgreSQL connection pool.
-    pub async fn get_record_tf_idf(&mut self, pool: &PgPool) -> Result<(), sqlx::Error> {
-        let result: Vec<QueryWordRecordInfo> = sqlx::query_as!(
-            QueryWordRecordInfo,
-            r#"
-            SELECT wri.record       AS record_id,
-                   wri.word         AS word,
-                   ri.trec_id       AS trec_id,
-                   ci.name          AS corpus_name,
-                   ci.id            AS corpus_id,
-                   wri.tf * wri.idf AS tf_idf,
-                   ri.uri           AS url,
-                   wri.appearances  AS appearances_r,
-                   ri.total_words   AS total_words_r
-            FROM word_record_index wri
-                     JOIN record_index ri
-                          ON wri.record = ri.id
-                     JOIN corpus_info ci
-                          ON ri.corpus_id = ci.id
-            WHERE wri.word = $1
+    let words: Vec<&str> = query_words.iter().map(|w| w.word.as_str()).collect();
+    let result = sqlx::query!(
+        r#"
+        SELECT b.word AS "word!", b.record AS "record!", b.boost AS "boost!"
+        FROM (SELECT (q.position - 1)::INT AS word,
+                     wri.record            AS record,
+                     (CASE
+                          WHEN BOOL_OR(rfi.field = 'title') THEN $2::DOUBLE PRECISION
+                          ELSE 1 END) *
+                     (CASE
+                          WHEN BOOL_OR(rfi.field = 'url') THEN $3::DOUBLE PRECISION
+                          ELSE 1 END)      AS boost
+              FROM UNNEST($1::TEXT[]) WITH ORDINALITY AS q(word, position)
+                       JOIN record_field_index rfi
+                            ON rfi.word = q.word
+                       JOIN word_record_index wri
+                            ON wri.word = rfi.word
+                                AND wri.record = rfi.record
+              GROUP BY q.position, wri.record) b
+        WHERE b.boost <> 1
         "#,
-            &self.word
-        )
-        .fetch_all(pool)
-        .await?;
-
-        self.word_record_tf_idf = result;
+        &words as &[&str],
+        boosts.title,
+        boosts.url
+    )
+    .fetch_all(pool)
+    .await?;
 
-        Ok(())
+    for row in result {
+        query_words[row.word as usize]
+            .record_boosts
+            .insert(row.record, row.boost);
     }
+
+    Ok(())
 }
 
-/// Calculate the TF-IDF of the given query word against the total corpus size.
+/// Get the TF-IDF of the query words, for each corpus that contains them.
 ///
 /// # Arguments
 ///
-/// * query_word        - The query word.
-/// * total_words       - The total words in the query.
-/// * pool              - The PostgreSQL connection pool.
-pub async fn calculate_tf_idf_for_query_word(
-    query_word: &mut QueryWord,
-    total_words: f64,
+/// * query_words   - The query words.
+/// * pool          - The PostgreSQL connection pool.
+pub async fn get_corpus_tf_idf(
+    query_words: &mut [QueryWord],
     pool: &PgPool,
 ) -> Result<(), sqlx::Error> {
-    let result = sqlx::query!(
+    let words: Vec<&str> = query_words.iter().map(|w| w.word.as_str()).collect();
+    let result: Vec<QueryWordCorpusInfo> = sqlx::query_as!(
+        QueryWordCorpusInfo,
         r#"
-        SELECT id
-        FROM corpus_info
-        WHERE id IN (SELECT corpus FROM word_corpus_index WHERE word = $1)
+        SELECT ci.id                            AS corpus_id,
+               ci.name                          AS corpus_name,
+               wci.word                         AS word,
+               wci.appearances                  AS appearances_c,
+               (SELECT SUM(wcii.appearances)
+                FROM word_corpus_index wcii
+                WHERE wcii.corpus = wci.corpus) AS total_words_c,
+               wci.tf * wci.idf                 AS tf_idf
+        FROM word_corpus_index wci
+                 JOIN corpus_info ci
+                      ON wci.corpus = ci.id
+        WHERE wci.word = ANY($1)
         "#,
-        &query_word.word
+        &words as &[&str]
     )
     .fetch_all(pool)
-    .await
-    .map_err(|e| error!("{}", e));
+    .await?;
 
-    let ids = result.unwrap();
+    for info in result {
+        for word in query_words.iter_mut().filter(|w| w.word == info.word) {
+            word.word_corpus_tf_idf.push(info.clone());
+        }
+    }
 
-    let mut weights: HashMap<i32, f64> = HashMap::new();
+    Ok(())
+}
 
-    for id in ids.iter() {
-        // Query
-        let result = sqlx::query!(
-            r#"
-        SELECT (1 + log((SELECT COUNT(*) FROM record_index fi) / COUNT(*)))
-                   AS idf
-        FROM record_index ci
-        WHERE ci.id IN (SELECT wci.record FROM word_record_index wci WHERE wci.word = $1)
-        AND ci.corpus_id = $2
-        "#,
-            &query_word.word,
-            &id.id
-        )
-        .fetch_one(pool)
-        .await
-        .map_err(|e| {
-            error!("{}", e);
-        });
-
-        // Match the result
-        match result {
-            Ok(res) => match res.idf {
-                Some(idf) => weights.insert(id.id, (1_f64 / total_words as f64) * idf),
-                None => weights.insert(id.id, 0_f64),
-            },
-            Err(_) => weights.insert(id.id, 0_f64),
-        };
+/// Get the `LIKE` pattern matching the texts containing a value.
+///
+/// # Arguments
+///
+/// * value     - The value.
+fn like_pattern(value: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in value.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
     }
+    pattern.push('%');
+    pattern
+}
 
-    query_word.tfidf = weights;
+/// Write the SQL condition matching the records of a field filter, on the `record_index` row
+/// `ri`.
+///
+/// `site:` uses the reversed host index, `inurl:` and `meta:` the trigram indexes.
+///
+/// # Arguments
+///
+/// * field     - The field.
+/// * value     - The value of the filter.
+/// * sql       - The query to write to.
+fn push_field(field: Field, value: &str, sql: &mut QueryBuilder<'_, Postgres>) {
+    match field {
+        // The host of the URL, or one of its subdomains.
+        Field::Site => {
+            let subdomain = format!(".{value}");
+            sql.push(r#"(REVERSE(ri.host) COLLATE "C" = REVERSE("#)
+                .push_bind(value.to_string())
+                .push(r#") OR (REVERSE(ri.host) COLLATE "C" > REVERSE("#)
+                .push_bind(subdomain.clone())
+                .push(r#") AND REVERSE(ri.host) COLLATE "C" < REVERSE("#)
+                .push_bind(subdomain)
+                .push(") || CHR(1114111)))");
+        }
+        Field::InUrl => {
+            sql.push("LOWER(ri.uri) LIKE ")
+                .push_bind(like_pattern(value));
+        }
+        Field::Corpus => {
+            sql.push(
+                "EXISTS(SELECT 1 FROM corpus_info ci WHERE ci.id = ri.corpus_id AND ci.name = ",
+            )
+            .push_bind(value.to_string())
+            .push(")");
+        }
+        Field::Meta => {
+            sql.push(
+                "EXISTS(SELECT 1 FROM record_meta rm WHERE rm.record = ri.id AND LOWER(rm.meta) LIKE ",
+            )
+            .push_bind(like_pattern(value))
+            .push(")");
+        }
+    }
+}
 
-    // Just indicate that it finished successfully.
-    Ok(())
+/// The records whose postings are scored.
+pub enum Filter<'a> {
+    /// The records matching a query expression.
+    Query(&'a Expr),
+    /// The given records.
+    Records(&'a [i32]),
 }
 
-/// Initial search layer.
+/// Implementation for `Filter` structure.
+impl Filter<'_> {
+    /// Write the SQL condition selecting the records of the filter, on the `record_index` row
+    /// `ri`.
+    ///
+    /// # Arguments
+    ///
+    /// * sql       - The query to write to.
+    fn push_condition(&self, sql: &mut QueryBuilder<'_, Postgres>) {
+        match self {
+            Filter::Query(expr) => push_expr(expr, sql),
+            Filter::Records(records) => {
+                sql.push("ri.id = ANY(")
+                    .push_bind(records.to_vec())
+                    .push(")");
+            }
+        }
+    }
+}
+
+/// Write the SQL condition matching the records of a query expression, on the `record_index` row
+/// `ri`.
 ///
-/// Handles splitting the query into words, finding and excluding the stopwords etc.
+/// The terms, phrases and proximity clauses are checked in `word_record_index`, and the field
+/// filters on the record, so that their postings and records are never loaded.
 ///
 /// # Arguments
 ///
-/// * pool      - The PostgreSQL connection pool.
-/// *query      - The query to execute.
-pub async fn perform_search(pool: &PgPool, query: &str) -> Result<Vec<SearchResult>, sqlx::Error> {
-    // Split the word into individual tokens.
-    let split = query.split_whitespace();
-    let wvector = split.collect::<Vec<&str>>();
-
-    // Create an object for each word in the query.
-    let mut query_words: Vec<QueryWord> = vec![];
-    for word in wvector {
-        if !is_stopword(word, pool).await? {
-            query_words.push(QueryWord::new(word.to_case(Case::Lower)));
-        } else {
-            warn!("Detected stopword: {}", &word);
+/// * expr      - The expression.
+/// * sql       - The query to write to.
+fn push_expr(expr: &Expr, sql: &mut QueryBuilder<'_, Postgres>) {
+    match expr {
+        Expr::Term(term) => {
+            sql.push(
+                "EXISTS(SELECT 1 FROM word_record_index m WHERE m.record = ri.id AND m.word = ",
+            )
+            .push_bind(term.clone())
+            .push(")");
+        }
+        Expr::Phrase(terms) => push_positional(terms, None, sql),
+        Expr::Near { terms, distance } => push_positional(terms, Some(*distance), sql),
+        Expr::Field { field, value } => push_field(*field, value, sql),
+        Expr::And(exprs) => push_joined(exprs, " AND ", "TRUE", sql),
+        Expr::Or(exprs) => push_joined(exprs, " OR ", "FALSE", sql),
+        Expr::Not(expr) => {
+            sql.push("NOT ");
+            push_expr(expr, sql);
+        }
+        Expr::Boost { expr, .. } => push_expr(expr, sql),
+        Expr::Clauses {
+            required,
+            optional,
+            prohibited,
+        } => {
+            sql.push("(");
+            match required.is_empty() {
+                true if !optional.is_empty() => push_joined(optional, " OR ", "FALSE", sql),
+                _ => push_joined(required, " AND ", "TRUE", sql),
+            }
+            for e in prohibited.iter() {
+                sql.push(" AND NOT ");
+                push_expr(e, sql);
+            }
+            sql.push(")");
         }
     }
+}
 
-    let total_words_in_query: f64 = query_words.len() as f64;
-    for word in query_words.iter_mut() {
-        calculate_tf_idf_for_query_word(word, total_words_in_query, pool)
-            .await
-            .expect(&format!(
-                "Failed to calculate TF-IDF for query word {}",
-                word.word
+/// Write the SQL condition matching the records of a phrase or proximity clause, on the
+/// `record_index` row `ri`.
+///
+/// The postings of the terms in the record are joined, so that only the records containing all
+/// of them are left, and their positions are checked by the database: the terms of a phrase must
+/// follow the position of the first one, and the terms of a proximity clause must all appear in
+/// a window starting at one of their positions.
+///
+/// # Arguments
+///
+/// * terms     - The terms, in query order.
+/// * distance  - The window of a proximity clause, `None` for a phrase.
+/// * sql       - The query to write to.
+fn push_positional(terms: &[String], distance: Option<u32>, sql: &mut QueryBuilder<'_, Postgres>) {
+    sql.push("EXISTS(SELECT 1 FROM word_record_index p0");
+    for (i, term) in terms.iter().enumerate().skip(1) {
+        sql.push(format!(
+            " JOIN word_record_index p{i} ON p{i}.record = p0.record AND p{i}.word = "
+        ))
+        .push_bind(term.clone());
+    }
+    sql.push(" WHERE p0.record = ri.id AND p0.word = ")
+        .push_bind(terms[0].clone());
+
+    match distance {
+        None => {
+            sql.push(" AND EXISTS(SELECT 1 FROM UNNEST(p0.positions) s(position) WHERE TRUE");
+            for i in 1..terms.len() {
+                sql.push(format!(" AND s.position + {i} = ANY(p{i}.positions)"));
+            }
+        }
+        Some(distance) => {
+            let all: Vec<String> = (0..terms.len())
+                .map(|i| format!("p{i}.positions"))
+                .collect();
+            sql.push(format!(
+                " AND EXISTS(SELECT 1 FROM UNNEST({}) s(position) WHERE TRUE",
+                all.join(" || ")
             ));
-        // Get the corpus TF-IDF
-        word.get_corpus_tf_idf(pool)
-            .await
-            .expect("Failed to compute TF-IDF for word for each corpus.");
-        word.get_record_tf_idf(pool)
-            .await
-            .expect("Failed to compute TF-IDF for word for each record.");
+            for i in 0..terms.len() {
+                sql.push(format!(
+                    " AND EXISTS(SELECT 1 FROM UNNEST(p{i}.positions) t(position) \
+                     WHERE t.position BETWEEN s.position AND s.position + {distance})"
+                ));
+            }
+        }
+    }
+    sql.push("))");
+}
+
+/// Write the SQL conditions of some expressions, joined by an operator.
+///
+/// # Arguments
+///
+/// * exprs     - The expressions.
+/// * operator  - The operator joining the conditions.
+/// * empty     - The condition written if there are no expressions.
+/// * sql       - The query to write to.
+fn push_joined(exprs: &[Expr], operator: &str, empty: &str, sql: &mut QueryBuilder<'_, Postgres>) {
+    if exprs.is_empty() {
+        sql.push(empty);
+        return;
+    }
+    sql.push("(");
+    for (i, expr) in exprs.iter().enumerate() {
+        if i > 0 {
+            sql.push(operator);
+        }
+        push_expr(expr, sql);
+    }
+    sql.push(")");
+}
+
+/// Get the records matching a filter.
+///
+/// # Arguments
+///
+/// * filter    - The filter.
+/// * pool      - The PostgreSQL connection pool.
+pub async fn get_matching_records(
+    filter: &Filter<'_>,
+    pool: &PgPool,
+) -> Result<Vec<i32>, sqlx::Error> {
+    let mut sql = QueryBuilder::new("SELECT ri.id FROM record_index ri WHERE ri.counted AND ");
+    filter.push_condition(&mut sql);
+    sql.build_query_scalar().fetch_all(pool).await
+}
+
+/// Stream the record postings of the query words.
+///
+/// The postings are handled as they arrive, so they are never loaded all together. The filter is
+/// evaluated by the database, along with the postings.
+///
+/// # Arguments
+///
+/// * query_words   - The query words.
+/// * filter        - The records matching the query, if not all the ones containing its words.
+/// * positions     - Load the positions of the words, for the proximity boost.
+/// * pool          - The PostgreSQL connection pool.
+/// * add           - Handles each posting, a scorer usually.
+pub async fn score_postings(
+    query_words: &[QueryWord],
+    filter: Option<&Filter<'_>>,
+    positions: bool,
+    pool: &PgPool,
+    mut add: impl FnMut(Posting),
+) -> Result<(), sqlx::Error> {
+    let words: Vec<String> = query_words.iter().map(|w| w.word.clone()).collect();
+    let mut sql = QueryBuilder::new(
+        r#"
+        SELECT (q.position - 1)::INT         AS word,
+               wri.record                    AS record,
+               ri.corpus_id                  AS corpus_id,
+               wri.tf * wri.idf              AS tf_idf,
+               wri.tf                        AS tf,
+               wri.idf                       AS idf,
+               wri.appearances               AS appearances,
+               COALESCE(ri.total_words, 0)   AS total_words,
+               (CASE
+                    WHEN "#,
+    );
+    sql.push_bind(positions)
+        .push(
+            r#" THEN wri.positions
+                    ELSE '{}'
+                   END)                      AS positions
+        FROM UNNEST("#,
+        )
+        .push_bind(words)
+        .push(
+            r#"::TEXT[]) WITH ORDINALITY AS q(word, position)
+                 JOIN word_record_index wri
+                      ON wri.word = q.word
+                 JOIN record_index ri
+                      ON wri.record = ri.id
+        WHERE ri.counted"#,
+        );
+    if let Some(filter) = filter {
+        sql.push(" AND ");
+        filter.push_condition(&mut sql);
+    }
+
+    let mut postings = sql.build_query_as::<Posting>().fetch(pool);
+    while let Some(posting) = postings.try_next().await? {
+        add(posting);
     }
 
-    let result = build_result(query_words);
+    Ok(())
+}
 
-    Ok(result)
+/// Get the information shown in the results, for the given records.
+///
+/// # Arguments
+///
+/// * records   - The record ids.
+/// * pool      - The PostgreSQL connection pool.
+pub async fn get_record_info(
+    records: &[i32],
+    pool: &PgPool,
+) -> Result<Vec<RecordInfo>, sqlx::Error> {
+    sqlx::query_as!(
+        RecordInfo,
+        r#"
+        SELECT ri.id      AS record,
+               ri.trec_id AS trec_id,
+               ri.uri     AS url,
+               ci.name    AS corpus_name
+        FROM record_index ri
+                 JOIN corpus_info ci
+                      ON ri.corpus_id = ci.id
+        WHERE ri.id = ANY($1)
+        "#,
+        records
+    )
+    .fetch_all(pool)
+    .await
 }
 
-/// Check if the given word is a stopword (frequency over 0.9).
+/// Build the snippets of the given records, from their stored text.
 ///
-/// Extremely useful in case of queries that could return the entire database.
+/// The text is analyzed again with the analyzer of the record's corpus, to find the query terms.
 ///
 /// # Arguments
 ///
-/// * word      - The word to search for.
+/// * records   - The record ids.
+/// * terms     - The query terms to highlight.
+/// * settings  - The search settings.
 /// * pool      - The PostgreSQL connection pool.
-pub async fn is_stopword(word: &str, pool: &PgPool) -> Result<bool, sqlx::Error> {
-    // First check if the given word exists.
-    let result = sqlx::query!(
+pub async fn get_snippets(
+    records: &[i32],
+    terms: &[&str],
+    settings: &SearchSettings,
+    pool: &PgPool,
+) -> Result<HashMap<i32, String>, WarcseError> {
+    let rows = sqlx::query!(
         r#"
-    SELECT EXISTS(SELECT * FROM word_index wi WHERE wi.word = $1);
-    "#,
-        &word
+        SELECT rt.record, rt.text, ci.analyzer
+        FROM record_text rt
+                 JOIN record_index ri ON ri.id = rt.record
+                 JOIN corpus_info ci ON ci.id = ri.corpus_id
+        WHERE rt.record = ANY($1)
+        "#,
+        records
     )
-    .fetch_one(pool)
+    .fetch_all(pool)
     .await?;
 
-    // Match the result
-    match result.exists {
-        Some(s) => {
-            if !s {
-                return Ok(false);
+    let terms: HashSet<&str> = terms.iter().copied().collect();
+    let mut analyzers: HashMap<String, Box<dyn Analyzer>> = HashMap::new();
+    let mut snippets = HashMap::new();
+    for row in rows {
+        if !analyzers.contains_key(&row.analyzer) {
+            let kind = row
+                .analyzer
+                .parse::<AnalyzerKind>()
+                .map_err(WarcseError::Internal)?;
+            analyzers.insert(row.analyzer.clone(), get_analyzer(kind));
+        }
+        let tokens = analyzers[&row.analyzer].tokens(&row.text);
+        snippets.insert(
+            row.record,
+            make_snippet(&row.text, &tokens, &terms, &settings.snippets),
+        );
+    }
+    Ok(snippets)
+}
+
+/// Get the analyzers of the indexed corpora, to analyze the queries like their records.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+pub async fn get_analyzers(pool: &PgPool) -> Result<Vec<AnalyzerKind>, WarcseError> {
+    let mut names =
+        sqlx::query_scalar!(r#"SELECT DISTINCT analyzer FROM corpus_info ORDER BY analyzer"#)
+            .fetch_all(pool)
+            .await?;
+    if names.is_empty() {
+        // Nothing is indexed yet, the query matches nothing anyway.
+        names.push(AnalyzerKind::default().name().to_string());
+    }
+    names
+        .iter()
+        .map(|name| name.parse::<AnalyzerKind>().map_err(WarcseError::Internal))
+        .collect()
+}
+
+/// The options of a search request.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchOptions {
+    /// The ranking model to use.
+    pub ranking: RankingModel,
+    /// Also search the spelling suggestions of the unknown words.
+    pub expand: bool,
+    /// Expand the query with the terms of its best results (pseudo-relevance feedback).
+    pub feedback: bool,
+    /// The boosts of the query words found in the title or URL of a record.
+    pub boosts: FieldBoosts,
+    /// Explain the scores of the results.
+    pub explain: bool,
+}
+
+/// Initial search layer.
+///
+/// Handles parsing the query, finding and excluding the stopwords etc. The records are selected
+/// by the query expression, and scored by its positive terms.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+/// * query     - The query to execute.
+/// * page      - The requested page of results.
+/// * options   - The options of the request.
+/// * settings  - The search settings.
+pub async fn perform_search(
+    pool: &PgPool,
+    query: &str,
+    page: &Page,
+    options: &SearchOptions,
+    settings: &SearchSettings,
+) -> Result<SearchPage, WarcseError> {
+    let kinds = get_analyzers(pool).await?;
+    let analyzers: Vec<Box<dyn Analyzer>> = kinds.iter().copied().map(get_analyzer).collect();
+    let expr = match parse_query(query, &analyzers)? {
+        Some(expr) => expr,
+        None => return Ok(SearchPage::default()),
+    };
+
+    // Exclude the stopwords, unless the query is made of stopwords only.
+    let mut terms: Vec<String> = expr.terms().into_iter().map(String::from).collect();
+    terms.sort_unstable();
+    terms.dedup();
+    let mut listed: Vec<String> = vec![];
+    for (kind, analyzer) in kinds.iter().zip(analyzers.iter()) {
+        let words = settings.stopwords.lists.get(kind.name());
+        for word in words.into_iter().flatten() {
+            listed.extend(analyzer.analyze(word));
+        }
+    }
+    let mut stopwords = find_stopwords(&terms, settings.stopwords.threshold, pool).await?;
+    stopwords.extend(listed.iter().filter(|term| terms.contains(term)).cloned());
+    let stopwords_removed = settings.stopwords.mode == StopwordMode::Remove;
+    let (expr, stopwords) = match expr.clone().without(&stopwords, stopwords_removed) {
+        Some(expr) => (expr, stopwords),
+        None => (expr, HashSet::new()),
+    };
+    // The stopwords kept in phrases are still matched.
+    let kept: HashSet<&str> = expr.terms().into_iter().collect();
+    let mut dropped_terms: Vec<String> = stopwords
+        .iter()
+        .filter(|word| !kept.contains(word.as_str()))
+        .cloned()
+        .collect();
+    dropped_terms.sort_unstable();
+    for word in dropped_terms.iter() {
+        warn!("Dropped stopword: {}", word);
+    }
+
+    // Suggest the indexed words close to the unknown ones.
+    let positive: Vec<String> = expr
+        .positive_terms()
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let terms: Vec<&str> = positive.iter().map(String::as_str).collect();
+    let suggestions = get_suggestions(&terms, &settings.spelling, pool).await?;
+    let did_you_mean = did_you_mean(query, &suggestions, analyzers[0].as_ref());
+
+    // Search the synonyms of the terms and, if asked to, the suggestions of the unknown words,
+    // with a lower weight.
+    let mut expansion = QueryExpansion::default();
+    for kind in kinds.iter() {
+        if let Some(synonyms) = settings.synonyms.dictionaries.get(kind.name()) {
+            for term in positive.iter() {
+                expansion.add(
+                    term,
+                    synonyms.get(term),
+                    settings.synonyms.weight,
+                    ExpansionSource::Synonym,
+                    &positive,
+                );
             }
         }
-        None => return Ok(false),
     }
+    if options.expand {
+        for suggestion in suggestions.iter() {
+            expansion.add(
+                &suggestion.term,
+                &suggestion.suggestions,
+                settings.spelling.expansion_weight,
+                ExpansionSource::Spelling,
+                &positive,
+            );
+        }
+    }
+    let expr = match expansion.alternatives.is_empty() {
+        true => expr,
+        false => expr.expand(&expansion.alternatives),
+    };
+
+    // Create an object for each scored word in the query. The stopwords kept in phrases only
+    // count for the matching.
+    let mut scored: Vec<(String, f64)> = expr
+        .scored_terms()
+        .into_iter()
+        .filter(|(word, _)| !stopwords.contains(*word))
+        .map(|(word, boost)| (String::from(word), boost * expansion.weight(word)))
+        .collect();
+
+    // The records matching the expression, unless it matches any of the words.
+    let filter = match expr.is_disjunction() {
+        true => None,
+        false => Some(Filter::Query(&expr)),
+    };
+
+    let hits = match (scored.is_empty(), filter) {
+        (false, filter) => {
+            let hits = score_query(pool, &scored, filter.as_ref(), options, settings).await?;
+            // Run the query again with the terms of its best results. Like the other words of a
+            // disjunction, they may match more records.
+            let feedback = match options.feedback {
+                true => {
+                    let mut exclude: Vec<String> = listed;
+                    exclude.extend(expr.terms().into_iter().map(String::from));
+                    get_feedback_terms(
+                        &hits,
+                        &exclude,
+                        settings.stopwords.threshold,
+                        &settings.feedback,
+                        pool,
+                    )
+                    .await?
+                }
+                false => vec![],
+            };
+            match feedback.is_empty() {
+                true => hits,
+                false => {
+                    scored.extend(feedback.iter().map(|e| (e.term.clone(), e.weight)));
+                    expansion.expansions.extend(feedback);
+                    score_query(pool, &scored, filter.as_ref(), options, settings).await?
+                }
+            }
+        }
+        // Only filters, the matching records are not scored.
+        (true, Some(filter)) if expr.is_bounded() => get_matching_records(&filter, pool)
+            .await?
+            .into_iter()
+            .map(|record| Hit {
+                record,
+                corpus_score: 0_f64,
+                record_score: 0_f64,
+                score_mixed: 0_f64,
+            })
+            .collect(),
+        // Only excluded terms, nothing to match.
+        (true, _) => vec![],
+    };
+
+    let (total_hits, hits, next_cursor) =
+        select_page(hits, page, |hit| (hit.score_mixed, hit.record));
+    let ranked = RankedPage {
+        total_hits,
+        hits,
+        next_cursor,
+    };
+
+    // Load the information of the page hits only.
+    let records: Vec<i32> = ranked.hits.iter().map(|hit| hit.record).collect();
+    let snippets = match settings.snippets.length {
+        0 => HashMap::new(),
+        _ => get_snippets(&records, &expr.positive_terms(), settings, pool).await?,
+    };
+    let explanations = match options.explain && !scored.is_empty() {
+        true => explain_query(pool, &scored, &records, options, settings).await?,
+        false => HashMap::new(),
+    };
+    let records = get_record_info(&records, pool).await?;
+
+    Ok(SearchPage {
+        dropped_terms,
+        suggestions,
+        did_you_mean,
+        expansions: expansion.expansions,
+        ..SearchPage::new(ranked, records, snippets, explanations)
+    })
+}
+
+/// Create the query words of a query, with their query weights, corpus TF-IDF and record boosts.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+/// * words     - The scored words of the query, with their weight.
+/// * boosts    - The boosts of the fields.
+async fn get_query_words(
+    pool: &PgPool,
+    words: &[(String, f64)],
+    boosts: &FieldBoosts,
+) -> Result<Vec<QueryWord>, sqlx::Error> {
+    let mut query_words: Vec<QueryWord> = words
+        .iter()
+        .map(|(word, weight)| QueryWord {
+            weight: *weight,
+            ..QueryWord::new(word.clone())
+        })
+        .collect();
+
+    calculate_query_weights(&mut query_words, pool).await?;
+    get_corpus_tf_idf(&mut query_words, pool).await?;
+    get_record_boosts(&mut query_words, boosts, pool).await?;
+    Ok(query_words)
+}
+
+/// Score the records matching a query.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+/// * words     - The scored words of the query, with their weight.
+/// * filter    - The records matching the query, if not all the ones containing its words.
+/// * options   - The options of the request.
+/// * settings  - The search settings.
+async fn score_query(
+    pool: &PgPool,
+    words: &[(String, f64)],
+    filter: Option<&Filter<'_>>,
+    options: &SearchOptions,
+    settings: &SearchSettings,
+) -> Result<Vec<Hit>, WarcseError> {
+    let query_words = get_query_words(pool, words, &options.boosts).await?;
+
+    let ranker = get_ranker(options.ranking, settings);
+    let stats = match ranker.requires_stats() {
+        true => Some(get_collection_stats(pool).await?),
+        false => None,
+    };
+    let mut scorer = ranker.scorer(&query_words, stats.as_ref());
+    let mut proximity = match settings.proximity_boost > 0_f64 && query_words.len() > 1 {
+        true => Some(Proximity::new(&query_words)),
+        false => None,
+    };
+    score_postings(&query_words, filter, proximity.is_some(), pool, |posting| {
+        scorer.add(&posting);
+        if let Some(proximity) = proximity.as_mut() {
+            proximity.add(&posting);
+        }
+    })
+    .await?;
+
+    let mut hits = scorer.hits();
+    if let Some(proximity) = proximity {
+        proximity.boost(&mut hits, settings.proximity_boost);
+    }
+    Ok(hits)
+}
 
+/// Explain the scores of some records of a query.
+///
+/// The records are scored again, alone, keeping their postings for the explanations. Their
+/// scores do not depend on the other records, so they are the ones of the first pass.
+///
+/// # Arguments
+///
+/// * pool      - The PostgreSQL connection pool.
+/// * words     - The scored words of the query, with their weight.
+/// * records   - The records to explain.
+/// * options   - The options of the request.
+/// * settings  - The search settings.
+async fn explain_query(
+    pool: &PgPool,
+    words: &[(String, f64)],
+    records: &[i32],
+    options: &SearchOptions,
+    settings: &SearchSettings,
+) -> Result<HashMap<i32, Explanation>, WarcseError> {
+    let query_words = get_query_words(pool, words, &options.boosts).await?;
+
+    let ranker = get_ranker(options.ranking, settings);
+    let stats = match ranker.requires_stats() {
+        true => Some(get_collection_stats(pool).await?),
+        false => None,
+    };
+    let scorer = ranker.scorer(&query_words, stats.as_ref());
+    let mut proximity = match settings.proximity_boost > 0_f64 && query_words.len() > 1 {
+        true => Some(Proximity::new(&query_words)),
+        false => None,
+    };
+    let filter = Filter::Records(records);
+    let mut postings: HashMap<i32, Vec<Posting>> = HashMap::new();
+    score_postings(
+        &query_words,
+        Some(&filter),
+        proximity.is_some(),
+        pool,
+        |posting| {
+            if let Some(proximity) = proximity.as_mut() {
+                proximity.add(&posting);
+            }
+            postings.entry(posting.record).or_default().push(posting);
+        },
+    )
+    .await?;
+
+    Ok(postings
+        .into_iter()
+        .map(|(record, postings)| {
+            let explanation = scorer.explain(record, &postings);
+            let explanation = match &proximity {
+                Some(proximity) => proximity.explain(record, explanation, settings.proximity_boost),
+                None => explanation,
+            };
+            (record, explanation)
+        })
+        .collect())
+}
+
+/// Find the stopwords (frequency over the threshold) among the given words.
+///
+/// Extremely useful in case of queries that could return the entire database.
+///
+/// # Arguments
+///
+/// * words     - The words to search for.
+/// * threshold - The fraction of the records a stopword appears in.
+/// * pool      - The PostgreSQL connection pool.
+pub async fn find_stopwords(
+    words: &[String],
+    threshold: f64,
+    pool: &PgPool,
+) -> Result<HashSet<String>, sqlx::Error> {
     let result = sqlx::query!(
         r#"
-    SELECT (CASE
-                WHEN wi.frequency > 0.9
-                    THEN TRUE
-                ELSE FALSE
-        END) AS is_stopword
-    FROM word_index wi
-    WHERE wi.word = $1
-    "#,
-        &word
+        SELECT wi.word
+        FROM word_index wi
+        WHERE wi.word = ANY($1)
+          AND wi.frequency > $2
+        "#,
+        words,
+        threshold
     )
-    .fetch_one(pool)
+    .fetch_all(pool)
     .await?;
 
-    // Match the result
-    Ok(match result.is_stopword {
-        Some(s) => s,
-        None => false,
-    })
+    Ok(result.into_iter().map(|r| r.word).collect())
 }
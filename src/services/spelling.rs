@@ -0,0 +1,106 @@
+use crate::analysis::Analyzer;
+use crate::configuration::SpellingSettings;
+use crate::domain::Suggestion;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+/// Find the spelling suggestions of the query terms missing from the index.
+///
+/// The suggestions are the indexed words sharing enough trigrams with the term, ranked by their
+/// similarity weighted by the log of their total appearances, so that the frequent words are
+/// preferred among the close ones.
+///
+/// # Arguments
+///
+/// * terms     - The analyzed query terms.
+/// * settings  - The spelling settings.
+/// * pool      - The PostgreSQL connection pool.
+pub async fn get_suggestions(
+    terms: &[&str],
+    settings: &SpellingSettings,
+    pool: &PgPool,
+) -> Result<Vec<Suggestion>, sqlx::Error> {
+    if settings.suggestions == 0 || terms.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let rows = sqlx::query!(
+        r#"
+        SELECT q.word       AS "term!",
+               s.word       AS "suggestion!",
+               q.position   AS "position!"
+        FROM UNNEST($1::TEXT[]) WITH ORDINALITY AS q(word, position)
+                 CROSS JOIN LATERAL (
+            SELECT wi.word
+            FROM word_index wi
+            WHERE wi.word % q.word
+              AND wi.total_appearances > 0
+              AND similarity(wi.word, q.word) >= $2
+            ORDER BY similarity(wi.word, q.word) * LN(1 + wi.total_appearances) DESC, wi.word
+            LIMIT $3
+            ) s
+        WHERE NOT EXISTS(SELECT 1 FROM word_index wi WHERE wi.word = q.word)
+        "#,
+        terms as &[&str],
+        settings.min_similarity as f32,
+        settings.suggestions as i64
+    )
+    .fetch_all(pool)
+    .await?;
+
+    // The rows of a term are in rank order, the terms are kept in query order.
+    let mut suggestions: Vec<(i64, Suggestion)> = vec![];
+    for row in rows {
+        match suggestions.iter_mut().find(|(_, s)| s.term == row.term) {
+            Some((_, suggestion)) => suggestion.suggestions.push(row.suggestion),
+            None => suggestions.push((
+                row.position,
+                Suggestion {
+                    term: row.term,
+                    suggestions: vec![row.suggestion],
+                },
+            )),
+        }
+    }
+    suggestions.sort_by_key(|(position, _)| *position);
+    Ok(suggestions.into_iter().map(|(_, s)| s).collect())
+}
+
+/// Rewrite a query with the unknown words replaced by their best suggestion.
+///
+/// # Arguments
+///
+/// * query         - The query, as sent.
+/// * suggestions   - The suggestions of the unknown terms.
+/// * analyzer      - The analyzer the terms come from.
+///
+/// # Returns
+///
+/// * `None` if no word of the query has a suggestion.
+pub fn did_you_mean(
+    query: &str,
+    suggestions: &[Suggestion],
+    analyzer: &dyn Analyzer,
+) -> Option<String> {
+    let best: HashMap<&str, &str> = suggestions
+        .iter()
+        .filter_map(|s| s.suggestions.first().map(|w| (s.term.as_str(), w.as_str())))
+        .collect();
+
+    let mut rewritten = String::with_capacity(query.len());
+    let mut end = 0;
+    for token in analyzer.tokens(query) {
+        if let Some(word) = best.get(token.term.as_str()) {
+            rewritten.push_str(&query[end..token.start]);
+            rewritten.push_str(word);
+            end = token.end;
+        }
+    }
+    match end {
+        0 => None,
+        _ => {
+            rewritten.push_str(&query[end..]);
+            Some(rewritten)
+        }
+    }
+}
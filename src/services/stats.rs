@@ -1,4 +1,5 @@
-use sqlx::PgPool;
+use crate::services::WORD_INDEX_CHANNEL;
+use sqlx::{PgConnection, PgPool};
 
 /// General information for the application.
 #[derive(Debug, serde::Serialize)]
@@ -30,3 +31,328 @@ pub async fn get_stats(pool: &PgPool) -> Result<StatsResponse, sqlx::Error> {
     .await?;
     Ok(status)
 }
+
+/// The key of the advisory lock held while the statistics of the index are updated ("warcse").
+pub const STATISTICS_LOCK: i64 = 0x7761_7263_7365;
+
+/// Take the statistics lock, until the end of the transaction.
+///
+/// The changes of the statistics are collected in `word_statistics_delta` and
+/// `corpus_statistics_delta` before being applied, so two concurrent updates would count the same
+/// records twice. Transactions that delete from the index before updating the statistics take the
+/// lock first, so that they never wait for it while holding the rows another update changes.
+///
+/// # Arguments
+///
+/// * conn  - `PgConnection` the PostgreSQL transaction.
+pub async fn lock_statistics(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    sqlx::query!("SELECT pg_advisory_xact_lock($1)", STATISTICS_LOCK)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Take records out of the index statistics, before they are deleted.
+///
+/// The postings of the counted records are subtracted from the statistics by the next
+/// `update_statistics` of the transaction, and the records are no longer searched.
+///
+/// # Arguments
+///
+/// * conn      - `PgConnection` the PostgreSQL transaction.
+/// * records   - The ids of the records in `record_index`.
+pub async fn discount_records(conn: &mut PgConnection, records: &[i32]) -> Result<(), sqlx::Error> {
+    lock_statistics(conn).await?;
+
+    sqlx::query!(
+        r#"
+        WITH discounted AS (
+            UPDATE record_index
+            SET counted = FALSE
+            WHERE id = ANY($1)
+              AND counted
+            RETURNING id, corpus_id),
+             words AS (
+                 INSERT INTO word_statistics_delta (word, corpus, appearances, records)
+                     SELECT wri.word, d.corpus_id, -SUM(wri.appearances), -COUNT(*)
+                     FROM word_record_index wri
+                              JOIN discounted d
+                                   ON wri.record = d.id
+                     GROUP BY wri.word, d.corpus_id)
+        INSERT
+        INTO corpus_statistics_delta (corpus, records)
+        SELECT corpus_id, -COUNT(*)
+        FROM discounted
+        GROUP BY corpus_id
+        "#,
+        records
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Update the global statistics of the index with the added and removed records.
+///
+/// The analyzed records that are not counted yet are added to the statistics, along with the
+/// records taken out by `discount_records`. Only the words of those records are updated in
+/// `word_index` and `word_corpus_index`, unless the number of records (or corpora) changes: the
+/// `idf` of every posting (or corpus) is then recomputed. Words left without any posting are
+/// removed from `word_index`. The `idf` of a word is `1 + log(N / n)`, where `N` is the number of
+/// counted records (or corpora) and `n` the number of those containing the word.
+///
+/// Should be called inside a transaction, so that the statistics are never seen half-updated.
+/// The statistics lock is held until the transaction ends, and `WORD_INDEX_CHANNEL` is notified
+/// once it is committed.
+///
+/// # Arguments
+///
+/// * conn  - `PgConnection` the PostgreSQL transaction.
+pub async fn update_statistics(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    lock_statistics(conn).await?;
+
+    // The new records, searched from now on.
+    sqlx::query!(
+        r#"
+        WITH counted AS (
+            UPDATE record_index
+            SET counted = TRUE
+            WHERE analyzed
+              AND NOT counted
+            RETURNING id, corpus_id),
+             words AS (
+                 INSERT INTO word_statistics_delta (word, corpus, appearances, records)
+                     SELECT wri.word, c.corpus_id, SUM(wri.appearances), COUNT(*)
+                     FROM word_record_index wri
+                              JOIN counted c
+                                   ON wri.record = c.id
+                     GROUP BY wri.word, c.corpus_id)
+        INSERT
+        INTO corpus_statistics_delta (corpus, records)
+        SELECT corpus_id, COUNT(*)
+        FROM counted
+        GROUP BY corpus_id
+        "#
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let corpora_before = count_corpora(conn).await?;
+
+    let records_changed = sqlx::query!(
+        r#"
+        UPDATE corpus_info ci
+        SET total_records = ci.total_records + d.records
+        FROM (SELECT corpus, SUM(records) AS records
+              FROM corpus_statistics_delta
+              GROUP BY corpus) d
+        WHERE ci.id = d.corpus
+          AND d.records <> 0
+        "#
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        > 0;
+
+    // Word statistics.
+    sqlx::query!(
+        r#"
+        UPDATE word_index wi
+        SET total_appearances = wi.total_appearances + d.appearances,
+            records           = wi.records + d.records
+        FROM (SELECT word, SUM(appearances) AS appearances, SUM(records) AS records
+              FROM word_statistics_delta
+              GROUP BY word) d
+        WHERE wi.word = d.word
+        "#
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO word_corpus_index (word, corpus, appearances)
+        SELECT word, corpus, SUM(appearances)
+        FROM word_statistics_delta
+        GROUP BY word, corpus
+        ON CONFLICT (word, corpus) DO UPDATE
+            SET appearances = word_corpus_index.appearances + EXCLUDED.appearances
+        "#
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM word_corpus_index wci
+        USING (SELECT DISTINCT word, corpus FROM word_statistics_delta) d
+        WHERE wci.word = d.word
+          AND wci.corpus = d.corpus
+          AND wci.appearances <= 0
+        "#
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM word_index wi
+        USING (SELECT DISTINCT word FROM word_statistics_delta) d
+        WHERE wi.word = d.word
+          AND NOT EXISTS(SELECT * FROM word_record_index wri WHERE wri.word = wi.word)
+        "#
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Record idf, of every word if the number of records changed.
+    if records_changed {
+        sqlx::query!(
+            r#"
+            UPDATE word_index wi
+            SET frequency = wi.records::DOUBLE PRECISION / n.total
+            FROM (SELECT SUM(total_records) AS total FROM corpus_info) n
+            WHERE n.total > 0
+            "#
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE word_record_index wri
+            SET idf = 1 - log(wi.frequency)
+            FROM word_index wi
+            WHERE wi.word = wri.word
+              AND wi.frequency > 0
+            "#
+        )
+        .execute(&mut *conn)
+        .await?;
+    } else {
+        sqlx::query!(
+            r#"
+            UPDATE word_index wi
+            SET frequency = wi.records::DOUBLE PRECISION / n.total
+            FROM (SELECT SUM(total_records) AS total FROM corpus_info) n
+            WHERE n.total > 0
+              AND wi.word IN (SELECT word FROM word_statistics_delta)
+            "#
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE word_record_index wri
+            SET idf = 1 - log(wi.frequency)
+            FROM word_index wi
+            WHERE wi.word = wri.word
+              AND wi.frequency > 0
+              AND wi.word IN (SELECT word FROM word_statistics_delta)
+            "#
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    // The spelling of each word shown by the completions, the most frequent one.
+    sqlx::query!(
+        r#"
+        UPDATE word_index wi
+        SET surface = s.surface
+        FROM (SELECT DISTINCT ON (ws.word) ws.word, ws.surface
+              FROM (SELECT wri.word, wri.surface, SUM(wri.appearances) AS appearances
+                    FROM word_record_index wri
+                    WHERE wri.surface IS NOT NULL
+                      AND wri.word IN (SELECT word FROM word_statistics_delta)
+                    GROUP BY wri.word, wri.surface) ws
+              ORDER BY ws.word, ws.appearances DESC, ws.surface) s
+        WHERE wi.word = s.word
+          AND wi.surface IS DISTINCT FROM s.surface
+        "#
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Corpus statistics, the tf of the changed corpora and the idf of every word if the number
+    // of corpora changed.
+    sqlx::query!(
+        r#"
+        UPDATE word_corpus_index wci
+        SET tf = wci.appearances::DOUBLE PRECISION / t.total_words
+        FROM (SELECT corpus, SUM(appearances) AS total_words
+              FROM word_corpus_index
+              WHERE corpus IN (SELECT corpus FROM word_statistics_delta)
+              GROUP BY corpus) t
+        WHERE t.corpus = wci.corpus
+        "#
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let corpora = count_corpora(conn).await?;
+    if corpora != corpora_before {
+        sqlx::query!(
+            r#"
+            UPDATE word_corpus_index wci
+            SET idf = 1 + log($1::BIGINT::DOUBLE PRECISION / c.corpora)
+            FROM (SELECT word, COUNT(*) AS corpora
+                  FROM word_corpus_index
+                  GROUP BY word) c
+            WHERE c.word = wci.word
+            "#,
+            corpora
+        )
+        .execute(&mut *conn)
+        .await?;
+    } else {
+        sqlx::query!(
+            r#"
+            UPDATE word_corpus_index wci
+            SET idf = 1 + log($1::BIGINT::DOUBLE PRECISION / c.corpora)
+            FROM (SELECT word, COUNT(*) AS corpora
+                  FROM word_corpus_index
+                  WHERE word IN (SELECT word FROM word_statistics_delta)
+                  GROUP BY word) c
+            WHERE c.word = wci.word
+            "#,
+            corpora
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query!("DELETE FROM word_statistics_delta")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query!("DELETE FROM corpus_statistics_delta")
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query!("SELECT pg_notify($1, '')", WORD_INDEX_CHANNEL)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+/// Count the corpora containing words, for the corpus `idf`.
+///
+/// # Arguments
+///
+/// * conn  - `PgConnection` the PostgreSQL transaction.
+async fn count_corpora(conn: &mut PgConnection) -> Result<i64, sqlx::Error> {
+    let count = sqlx::query!(
+        r#"
+        SELECT COUNT(*) AS "count!"
+        FROM corpus_info ci
+        WHERE EXISTS(SELECT * FROM word_corpus_index wci WHERE wci.corpus = ci.id)
+        "#
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(count.count)
+}
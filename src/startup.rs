@@ -1,34 +1,109 @@
-use crate::routes::{query, stats, status};
+use crate::configuration::{AnalysisSettings, SearchSettings};
+use crate::domain::Completions;
+use crate::error::WarcseError;
+use crate::routes::{
+    delete_corpus, delete_record, get_record, get_record_headers, query, stats, status, suggest,
+};
+use crate::services::refresh_completions;
 use actix_cors::Cors;
 use actix_web::dev::Server;
 use actix_web::web::Data;
-use actix_web::{web, App, HttpServer};
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
 use sqlx::PgPool;
 use std::net::TcpListener;
+use std::sync::RwLock;
 
 /// Run the application in the given port.
 ///
 /// # Arguments
 ///
-/// * listener  - `TcpListener` to bind to.
-/// * db_pool   - `PgPool` the PostgreSQL pool.
+/// * listener      - `TcpListener` to bind to.
+/// * db_pool       - `PgPool` the PostgreSQL pool.
+/// * search        - `SearchSettings` the search configuration.
+/// * analysis      - `AnalysisSettings` the analysis configuration, for the cached pages.
+/// * completions   - `Completions` the completions of the indexed words, reloaded after each
+///   update of the index.
 ///
 /// # Returns
 ///
 /// * `Server` if the bind was successful.
-/// * `std::io::Error` if the app did not start successfully.
-pub fn run(listener: TcpListener, db_pool: PgPool) -> Result<Server, std::io::Error> {
+/// * `std::io::Error` if the app did not start successfully, e.g. a synonym dictionary can not be
+///   read.
+pub fn run(
+    listener: TcpListener,
+    db_pool: PgPool,
+    mut search: SearchSettings,
+    analysis: AnalysisSettings,
+    completions: Completions,
+) -> Result<Server, std::io::Error> {
+    search.synonyms.load()?;
+    let completions = Data::new(RwLock::new(completions));
+    tokio::spawn(refresh_completions(
+        db_pool.clone(),
+        completions.clone().into_inner(),
+        search.completion.max_limit,
+    ));
     let db_pool = Data::new(db_pool);
+    let search = Data::new(search);
+    let analysis = Data::new(analysis);
     let server = HttpServer::new(move || {
         App::new()
-            .wrap(Cors::permissive())
+            .wrap(cors())
             .service(actix_files::Files::new("/static/", "ui/dist").index_file("index.html"))
             .route("/status", web::get().to(status))
             .route("/query", web::post().to(query))
+            .route("/suggest", web::get().to(suggest))
             .route("/stats", web::get().to(stats))
+            .route("/corpus/{id}", web::delete().to(delete_corpus))
+            .route("/record/{trec_id}", web::get().to(get_record))
+            .route("/record/{trec_id}", web::delete().to(delete_record))
+            .route(
+                "/record/{trec_id}/headers",
+                web::get().to(get_record_headers),
+            )
+            .default_service(web::to(not_found))
             .app_data(db_pool.clone())
+            .app_data(search.clone())
+            .app_data(analysis.clone())
+            .app_data(completions.clone())
+            // Malformed requests get the same JSON errors as the rest of the API.
+            .app_data(
+                web::JsonConfig::default()
+                    .error_handler(|e, _req| WarcseError::BadRequest(e.to_string()).into()),
+            )
+            .app_data(
+                web::PathConfig::default()
+                    .error_handler(|e, _req| WarcseError::BadRequest(e.to_string()).into()),
+            )
+            .app_data(
+                web::QueryConfig::default()
+                    .error_handler(|e, _req| WarcseError::BadRequest(e.to_string()).into()),
+            )
     })
     .listen(listener)?
     .run();
     Ok(server)
 }
+
+/// The CORS policy of the API.
+///
+/// Any origin may search and read the records, but the `DELETE` routes are not exposed to
+/// cross-origin requests: browsers check them with a preflight request, which is refused, so a web
+/// page cannot delete the corpora of a server running locally.
+fn cors() -> Cors {
+    Cors::default()
+        .allow_any_origin()
+        .allowed_methods(["GET", "POST"])
+        .allow_any_header()
+        .expose_any_header()
+        .max_age(3600)
+}
+
+/// Fallback for the unknown routes.
+///
+/// # Arguments
+///
+/// * req   - `HttpRequest` the HTTP Request.
+async fn not_found(req: HttpRequest) -> Result<HttpResponse, WarcseError> {
+    Err(WarcseError::NotFound(format!("{} not found", req.path())))
+}
@@ -0,0 +1,46 @@
+use std::fmt;
+
+/// Errors raised while reading a WARC file.
+#[derive(Debug)]
+pub enum WarcError {
+    /// The underlying reader failed.
+    Io(std::io::Error),
+    /// The record does not start with a `WARC/<version>` line.
+    InvalidVersion(String),
+    /// A header line could not be parsed as `Name: value`.
+    InvalidHeader(String),
+    /// The record has no (or an unparsable) `Content-Length` header.
+    MissingContentLength,
+    /// The file ended in the middle of a record.
+    Truncated,
+}
+
+impl fmt::Display for WarcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WarcError::Io(e) => write!(f, "I/O error: {}", e),
+            WarcError::InvalidVersion(line) => write!(f, "Invalid WARC version line: {:?}", line),
+            WarcError::InvalidHeader(line) => write!(f, "Invalid WARC header line: {:?}", line),
+            WarcError::MissingContentLength => write!(f, "Missing or invalid Content-Length"),
+            WarcError::Truncated => write!(f, "Unexpected end of file inside a record"),
+        }
+    }
+}
+
+impl std::error::Error for WarcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WarcError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for WarcError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => WarcError::Truncated,
+            _ => WarcError::Io(e),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use flate2::bufread::GzDecoder;
+use std::cell::RefCell;
+use std::io::{BufRead, Read};
+use std::mem;
+use std::rc::Rc;
+
+/// The start of a gzip member.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Member {
+    /// The offset of the member in the compressed stream.
+    pub compressed: u64,
+    /// The offset of the member content in the decompressed stream.
+    pub decompressed: u64,
+}
+
+/// The members read so far, shared with the `WarcReader`.
+pub(crate) type Members = Rc<RefCell<Vec<Member>>>;
+
+/// A `BufRead` counting the bytes consumed from the underlying stream.
+struct Counting<R> {
+    /// The underlying stream.
+    inner: R,
+    /// The number of bytes consumed.
+    count: u64,
+}
+
+impl<R: BufRead> Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for Counting<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.count += amt as u64;
+        self.inner.consume(amt)
+    }
+}
+
+/// The position of the decoder in the sequence of members.
+enum State<R> {
+    /// Between two members.
+    Between(Counting<R>),
+    /// Inside a member.
+    Inside(GzDecoder<Counting<R>>),
+    /// At the end of the stream.
+    Done,
+}
+
+/// Decompresses a sequence of gzip members, recording where each of them starts.
+pub(crate) struct GzipMembers<R> {
+    /// The position in the sequence of members.
+    state: State<R>,
+    /// The number of bytes decompressed so far.
+    decompressed: u64,
+    /// The members read so far.
+    members: Members,
+}
+
+/// Implementation for `GzipMembers` structure.
+impl<R: BufRead> GzipMembers<R> {
+    /// Create a new `GzipMembers` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * reader    - The compressed stream.
+    /// * members   - The list to record the members into.
+    pub fn new(reader: R, members: Members) -> GzipMembers<R> {
+        GzipMembers {
+            state: State::Between(Counting {
+                inner: reader,
+                count: 0,
+            }),
+            decompressed: 0,
+            members,
+        }
+    }
+}
+
+impl<R: BufRead> Read for GzipMembers<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match mem::replace(&mut self.state, State::Done) {
+                State::Between(mut reader) => {
+                    if reader.fill_buf()?.is_empty() {
+                        return Ok(0);
+                    }
+                    self.members.borrow_mut().push(Member {
+                        compressed: reader.count,
+                        decompressed: self.decompressed,
+                    });
+                    self.state = State::Inside(GzDecoder::new(reader));
+                }
+                State::Inside(mut decoder) => {
+                    let read = decoder.read(buf)?;
+                    if read == 0 {
+                        // The end of the member, the next one may follow.
+                        self.state = State::Between(decoder.into_inner());
+                        continue;
+                    }
+                    self.decompressed += read as u64;
+                    self.state = State::Inside(decoder);
+                    return Ok(read);
+                }
+                State::Done => return Ok(0),
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::warc::WarcRecord;
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use std::borrow::Cow;
+use std::io::{self, Read};
+
+/// The HTTP response archived in a `response` record.
+#[derive(Debug)]
+pub struct HttpPayload<'a> {
+    /// The status code of the response.
+    pub status: u16,
+    /// The header fields of the response, as found in the record.
+    pub headers: Vec<(String, String)>,
+    /// The body of the response.
+    pub body: &'a [u8],
+}
+
+/// Implementation for `HttpPayload` structure.
+impl<'a> HttpPayload<'a> {
+    /// Get the value of a header field.
+    ///
+    /// Field names are case-insensitive. If the field is repeated, the first value is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * name      - The field name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The `Content-Type` of the response.
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("Content-Type")
+    }
+
+    /// Check if the body is sent in chunks (`Transfer-Encoding: chunked`).
+    pub fn is_chunked(&self) -> bool {
+        self.header("Transfer-Encoding").is_some_and(|encoding| {
+            encoding
+                .split(',')
+                .any(|coding| coding.trim().eq_ignore_ascii_case("chunked"))
+        })
+    }
+
+    /// Get the body without its chunk framing.
+    ///
+    /// Some crawlers store the body as sent, others store it de-chunked but keep the
+    /// `Transfer-Encoding` header, so a body that is not validly chunked is returned as it is.
+    pub fn dechunked_body(&self) -> Cow<'a, [u8]> {
+        match self.is_chunked() {
+            true => dechunk(self.body).map_or(Cow::Borrowed(self.body), Cow::Owned),
+            false => Cow::Borrowed(self.body),
+        }
+    }
+
+    /// Get the body without its chunk framing and its compression.
+    ///
+    /// The codings of `Transfer-Encoding` and `Content-Encoding` are undone in reverse order.
+    /// Like chunked bodies, compressed bodies may have been stored decompressed, so a `gzip` body
+    /// that does not start like a gzip stream is returned as it is.
+    ///
+    /// # Arguments
+    ///
+    /// * limit     - The maximum size of a decompressed body, so that a small record cannot
+    ///   expand to an unbounded one.
+    ///
+    /// # Returns
+    ///
+    /// * An error of kind `Unsupported` if a coding is not `gzip`, `deflate` or `identity`, of
+    ///   kind `InvalidData` if the body exceeds `limit` once decompressed, or the decompression
+    ///   error.
+    pub fn decoded_body(&self, limit: usize) -> io::Result<Cow<'a, [u8]>> {
+        let codings: Vec<String> = ["Transfer-Encoding", "Content-Encoding"]
+            .into_iter()
+            .filter_map(|name| self.header(name))
+            .flat_map(|value| value.split(','))
+            .map(|coding| coding.trim().to_ascii_lowercase())
+            .filter(|coding| !coding.is_empty() && coding != "chunked" && coding != "identity")
+            .collect();
+
+        let mut body = self.dechunked_body();
+        for coding in codings.iter().rev() {
+            let decoded = match coding.as_str() {
+                "gzip" | "x-gzip" => {
+                    if !body.starts_with(&[0x1f, 0x8b]) {
+                        continue;
+                    }
+                    read_limited(GzDecoder::new(&body[..]), limit)?
+                }
+                "deflate" => match is_zlib(&body) {
+                    true => read_limited(ZlibDecoder::new(&body[..]), limit)?,
+                    // Some servers send raw deflate data.
+                    false => read_limited(DeflateDecoder::new(&body[..]), limit)?,
+                },
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        format!("Unsupported encoding: {}", coding),
+                    ))
+                }
+            };
+            body = Cow::Owned(decoded);
+        }
+        Ok(body)
+    }
+}
+
+/// Read a decompressed stream, up to a maximum size.
+///
+/// # Arguments
+///
+/// * reader    - The stream.
+/// * limit     - The maximum size of the data.
+///
+/// # Returns
+///
+/// * An error of kind `InvalidData` if the stream is longer than `limit`.
+fn read_limited<R: Read>(reader: R, limit: usize) -> io::Result<Vec<u8>> {
+    let mut data = vec![];
+    reader.take(limit as u64 + 1).read_to_end(&mut data)?;
+    match data.len() > limit {
+        true => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Decompressed body larger than {} bytes", limit),
+        )),
+        false => Ok(data),
+    }
+}
+
+/// Check if data starts with a zlib header.
+///
+/// # Arguments
+///
+/// * data      - The data.
+fn is_zlib(data: &[u8]) -> bool {
+    match data {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0,
+        _ => false,
+    }
+}
+
+/// Join the chunks of a chunked body.
+///
+/// # Arguments
+///
+/// * body      - The chunked body.
+///
+/// # Returns
+///
+/// * `None` if the body is not validly chunked.
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut data = vec![];
+    loop {
+        // The chunk size, in hexadecimal, possibly followed by extensions.
+        let end = find(body, b"\n")?;
+        let line = std::str::from_utf8(&body[..end]).ok()?;
+        let size = line.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        body = &body[end + 1..];
+        if size == 0 {
+            // The trailer fields are ignored.
+            return Some(data);
+        }
+
+        data.extend_from_slice(body.get(..size)?);
+        body = &body[size..];
+        body = body
+            .strip_prefix(b"\r\n")
+            .or_else(|| body.strip_prefix(b"\n"))?;
+    }
+}
+
+/// Implementation for `WarcRecord` structure.
+impl WarcRecord {
+    /// Parse the block of a `response` record as an HTTP response.
+    ///
+    /// # Returns
+    ///
+    /// * `None` if the block does not start with an HTTP status line.
+    pub fn http_payload(&self) -> Option<HttpPayload<'_>> {
+        let block = self.block.as_slice();
+        // The header ends at the first empty line, some servers only use `\n`.
+        let (head, body) = match find(block, b"\r\n\r\n") {
+            Some(end) => (&block[..end], &block[end + 4..]),
+            None => match find(block, b"\n\n") {
+                Some(end) => (&block[..end], &block[end + 2..]),
+                None => (block, &block[block.len()..]),
+            },
+        };
+
+        let head = String::from_utf8_lossy(head);
+        let mut lines = head.lines();
+        let status = lines
+            .next()?
+            .strip_prefix("HTTP/")?
+            .split_whitespace()
+            .nth(1)?
+            .parse()
+            .ok()?;
+        let mut headers: Vec<(String, String)> = vec![];
+        for line in lines {
+            if line.starts_with([' ', '\t']) {
+                // Folded continuation of the previous field.
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+
+        Some(HttpPayload {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Find the first occurrence of a byte sequence.
+///
+/// # Arguments
+///
+/// * haystack  - The bytes to search in.
+/// * needle    - The bytes to search for.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
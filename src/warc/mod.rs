@@ -0,0 +1,14 @@
+/// WARC file parsing.
+///
+/// Streams the records of WARC/0.18, WARC/1.0 and WARC/1.1 files, either plain or
+/// compressed as a sequence of gzip members.
+mod error;
+mod gzip;
+mod http;
+mod reader;
+mod record;
+
+pub use error::*;
+pub use http::*;
+pub use reader::*;
+pub use record::*;
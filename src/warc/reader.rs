@@ -0,0 +1,221 @@
+use crate::warc::gzip::{GzipMembers, Members};
+use crate::warc::{RecordOffset, WarcError, WarcRecord};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// The magic bytes at the start of every gzip member.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// A streaming WARC reader.
+///
+/// Yields the records of the underlying stream one at a time, so that arbitrarily large files
+/// can be processed without loading them in memory.
+pub struct WarcReader<R> {
+    /// The (decompressed) input stream.
+    reader: R,
+    /// Set after the first error, to stop the iteration.
+    failed: bool,
+    /// The number of bytes read from the (decompressed) stream.
+    position: u64,
+    /// The gzip members of a compressed file.
+    members: Option<Members>,
+}
+
+/// Implementation for `WarcReader` over files.
+impl WarcReader<Box<dyn BufRead>> {
+    /// Open a WARC file.
+    ///
+    /// Files starting with the gzip magic bytes are decompressed on the fly, member by member.
+    ///
+    /// # Arguments
+    ///
+    /// * path      - The path of the WARC file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, WarcError> {
+        let mut file = BufReader::new(File::open(path)?);
+        if file.fill_buf()?.starts_with(&GZIP_MAGIC) {
+            let members = Members::default();
+            let reader = BufReader::new(GzipMembers::new(file, members.clone()));
+            let mut reader = WarcReader::new(Box::new(reader) as Box<dyn BufRead>);
+            reader.members = Some(members);
+            Ok(reader)
+        } else {
+            Ok(WarcReader::new(Box::new(file)))
+        }
+    }
+
+    /// Read a single record of a WARC file.
+    ///
+    /// The gzip members are decompressed from the one the record starts in, and only until its
+    /// block is read, so that a record split over several members is read whole.
+    ///
+    /// # Arguments
+    ///
+    /// * path      - The path of the WARC file.
+    /// * offset    - The offset of the record, as found when reading the file.
+    pub fn read_at<P: AsRef<Path>>(path: P, offset: RecordOffset) -> Result<WarcRecord, WarcError> {
+        let mut file = BufReader::new(File::open(path)?);
+        file.seek(SeekFrom::Start(offset.file))?;
+        let mut reader: Box<dyn BufRead> = if file.fill_buf()?.starts_with(&GZIP_MAGIC) {
+            Box::new(BufReader::new(GzipMembers::new(file, Members::default())))
+        } else {
+            Box::new(file)
+        };
+        let skipped = std::io::copy(&mut (&mut reader).take(offset.member), &mut std::io::sink())?;
+        if skipped < offset.member {
+            return Err(WarcError::Truncated);
+        }
+
+        let record = WarcReader::new(reader)
+            .read_record()?
+            .ok_or(WarcError::Truncated)?;
+        Ok(WarcRecord { offset, ..record })
+    }
+}
+
+/// Implementation for `WarcReader` structure.
+impl<R: BufRead> WarcReader<R> {
+    /// Create a new `WarcReader` over an uncompressed stream.
+    ///
+    /// # Arguments
+    ///
+    /// * reader    - The stream to read the records from.
+    pub fn new(reader: R) -> WarcReader<R> {
+        WarcReader {
+            reader,
+            failed: false,
+            position: 0,
+            members: None,
+        }
+    }
+
+    /// Read the next record of the stream.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(WarcRecord)` if a record was read.
+    /// * `None` if the end of the stream was reached.
+    /// * `WarcError` if the record is malformed or the stream could not be read.
+    pub fn read_record(&mut self) -> Result<Option<WarcRecord>, WarcError> {
+        // Skip the blank lines separating the records.
+        let (start, version_line) = loop {
+            let start = self.position;
+            match self.read_line()? {
+                None => return Ok(None),
+                Some(line) if line.is_empty() => continue,
+                Some(line) => break (start, line),
+            }
+        };
+        let version = match version_line.strip_prefix("WARC/") {
+            Some(version) => version.trim().to_string(),
+            None => return Err(WarcError::InvalidVersion(version_line)),
+        };
+
+        // Read the named fields up to the first empty line.
+        let mut headers: Vec<(String, String)> = vec![];
+        loop {
+            let line = self.read_line()?.ok_or(WarcError::Truncated)?;
+            if line.is_empty() {
+                break;
+            }
+            if line.starts_with([' ', '\t']) {
+                // Folded continuation of the previous field.
+                match headers.last_mut() {
+                    Some((_, value)) => {
+                        value.push(' ');
+                        value.push_str(line.trim());
+                    }
+                    None => return Err(WarcError::InvalidHeader(line)),
+                }
+                continue;
+            }
+            match line.split_once(':') {
+                Some((name, value)) => {
+                    headers.push((name.trim().to_string(), value.trim().to_string()))
+                }
+                None => return Err(WarcError::InvalidHeader(line)),
+            }
+        }
+
+        let length: u64 = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("Content-Length"))
+            .and_then(|(_, value)| value.parse().ok())
+            .ok_or(WarcError::MissingContentLength)?;
+
+        // Read the block, without trusting the length for the initial allocation.
+        let mut block = Vec::with_capacity(length.min(1 << 20) as usize);
+        (&mut self.reader).take(length).read_to_end(&mut block)?;
+        self.position += block.len() as u64;
+        if (block.len() as u64) < length {
+            return Err(WarcError::Truncated);
+        }
+
+        Ok(Some(WarcRecord {
+            version,
+            headers,
+            block,
+            offset: self.locate(start),
+        }))
+    }
+
+    /// Get the offset of a record in the file, from its position in the decompressed stream.
+    ///
+    /// # Arguments
+    ///
+    /// * position  - The position of the record in the decompressed stream.
+    fn locate(&self, position: u64) -> RecordOffset {
+        let member = self.members.as_ref().and_then(|members| {
+            members
+                .borrow()
+                .iter()
+                .rev()
+                .find(|member| member.decompressed <= position)
+                .copied()
+        });
+        match member {
+            Some(member) => RecordOffset {
+                file: member.compressed,
+                member: position - member.decompressed,
+            },
+            None => RecordOffset {
+                file: position,
+                member: 0,
+            },
+        }
+    }
+
+    /// Read a single line, without the line terminator.
+    ///
+    /// Returns `None` at the end of the stream.
+    fn read_line(&mut self) -> Result<Option<String>, WarcError> {
+        let mut buffer = vec![];
+        let read = self.reader.read_until(b'\n', &mut buffer)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        self.position += read as u64;
+        while buffer.last().is_some_and(|c| *c == b'\n' || *c == b'\r') {
+            buffer.pop();
+        }
+        Ok(Some(String::from_utf8_lossy(&buffer).into_owned()))
+    }
+}
+
+/// Iterate over the records of the stream.
+///
+/// The iteration stops after the first error.
+impl<R: BufRead> Iterator for WarcReader<R> {
+    type Item = Result<WarcRecord, WarcError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let record = self.read_record().transpose();
+        if let Some(Err(_)) = record {
+            self.failed = true;
+        }
+        record
+    }
+}
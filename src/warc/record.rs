@@ -0,0 +1,70 @@
+/// A single WARC record.
+///
+/// Contains the version, the named header fields (in file order) and the raw block.
+#[derive(Debug, Clone)]
+pub struct WarcRecord {
+    /// The WARC version of the record (e.g. `0.18`, `1.0`).
+    pub version: String,
+    /// The named header fields, as found in the file.
+    pub headers: Vec<(String, String)>,
+    /// The record block (`Content-Length` bytes).
+    pub block: Vec<u8>,
+    /// The location of the record in its file.
+    pub offset: RecordOffset,
+}
+
+/// The location of a record in a WARC file.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RecordOffset {
+    /// The offset of the gzip member containing the record in compressed files, of the record
+    /// otherwise.
+    pub file: u64,
+    /// The offset of the record in the decompressed member (0 in plain files).
+    pub member: u64,
+}
+
+/// Implementation for `WarcRecord` structure.
+impl WarcRecord {
+    /// Get the value of a header field.
+    ///
+    /// Field names are case-insensitive. If the field is repeated, the first value is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * name      - The field name.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The `WARC-Type` of the record (e.g. `response`, `warcinfo`).
+    pub fn warc_type(&self) -> Option<&str> {
+        self.header("WARC-Type")
+    }
+
+    /// The `WARC-TREC-ID` of the record (only present in TREC collections).
+    pub fn trec_id(&self) -> Option<&str> {
+        self.header("WARC-TREC-ID")
+    }
+
+    /// The `WARC-Record-ID` of the record.
+    pub fn record_id(&self) -> Option<&str> {
+        self.header("WARC-Record-ID")
+    }
+
+    /// The `WARC-Target-URI` of the record, without the enclosing `<>` some writers add.
+    pub fn target_uri(&self) -> Option<&str> {
+        self.header("WARC-Target-URI").map(|uri| {
+            uri.strip_prefix('<')
+                .and_then(|u| u.strip_suffix('>'))
+                .unwrap_or(uri)
+        })
+    }
+
+    /// The length of the record block in bytes.
+    pub fn content_length(&self) -> usize {
+        self.block.len()
+    }
+}
@@ -0,0 +1,61 @@
+use warcse::analysis::{get_analyzer, AnalyzerKind};
+
+/// Analyze a text with the given analyzer.
+fn analyze(kind: AnalyzerKind, text: &str) -> Vec<String> {
+    get_analyzer(kind).analyze(text)
+}
+
+/// The standard analyzer splits on the word boundaries, folds the case and strips the accents.
+#[test]
+fn test_standard_analyzer() {
+    let terms = analyze(
+        AnalyzerKind::Standard,
+        "Café in São Paulo: foo_bar, don't (3.14)!",
+    );
+
+    // Assert
+    assert_eq!(
+        terms,
+        ["cafe", "in", "sao", "paulo", "foo_bar", "don't", "3.14"]
+    );
+}
+
+/// The English analyzer stems the words.
+#[test]
+fn test_english_analyzer() {
+    let terms = analyze(AnalyzerKind::English, "The Jaguars hunting in rainforests");
+
+    // Assert
+    assert_eq!(terms, ["the", "jaguar", "hunt", "in", "rainforest"]);
+}
+
+/// The accents are stripped after stemming, so the stemmer sees the original words.
+#[test]
+fn test_french_analyzer() {
+    let terms = analyze(AnalyzerKind::French, "Les élèves étudiaient");
+
+    // Assert
+    assert!(terms.iter().all(|term| term.is_ascii()));
+    assert_eq!(
+        terms,
+        analyze(AnalyzerKind::French, "les ELEVES etudiaient")
+    );
+}
+
+/// The simple analyzer splits on non-alphanumeric characters, like the first indexed corpora.
+#[test]
+fn test_simple_analyzer() {
+    let terms = analyze(AnalyzerKind::Simple, "Jaguars: foo_bar");
+
+    // Assert
+    assert_eq!(terms, ["jaguars", "foo", "bar"]);
+}
+
+/// The analyzers are recorded by name.
+#[test]
+fn test_analyzer_names() {
+    for kind in AnalyzerKind::ALL {
+        assert_eq!(kind.name().parse::<AnalyzerKind>(), Ok(kind));
+    }
+    assert!("klingon".parse::<AnalyzerKind>().is_err());
+}
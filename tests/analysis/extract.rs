@@ -0,0 +1,95 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use warcse::analysis::{extract_document, extract_html, Document};
+use warcse::configuration::AnalysisSettings;
+use warcse::warc::{RecordOffset, WarcReader, WarcRecord};
+
+/// The markup and boilerplate are removed, the title and description are kept.
+#[test]
+fn test_extract_html() {
+    let document = extract_html(
+        r#"<!DOCTYPE html><html><head><title> Jaguar &amp; Co </title>
+        <meta content="Big cats" name=description><style>p { color: red; }</style></head>
+        <body><nav><a href="/">Home</a></nav><!-- hidden --><h1>Jaguars</h1><p>The <b>jag</b>uar
+        hunts.<br/>At night &lt;3</p><script>var x = "</p>";</script><footer>Copyright</footer>
+        </body></html>"#,
+    );
+
+    // Assert
+    assert_eq!(
+        document,
+        Document {
+            title: Some(String::from("Jaguar & Co")),
+            description: Some(String::from("Big cats")),
+            text: String::from("Jaguars The jaguar hunts. At night <3"),
+        }
+    );
+}
+
+/// The chunked and compressed bodies are decoded, the ones with unknown encodings dropped, and
+/// the ones stored decompressed kept.
+#[test]
+fn test_extract_encoded() {
+    let records = WarcReader::open(format!(
+        "{}/tests/fixtures/encoded-sample.warc",
+        env!("CARGO_MANIFEST_DIR")
+    ))
+    .expect("Failed to open fixture.")
+    .collect::<Result<Vec<WarcRecord>, _>>()
+    .expect("Failed to parse fixture.");
+
+    // Act
+    let documents: Vec<Document> = records
+        .iter()
+        .map(|record| extract_document(record, AnalysisSettings::default().max_body_size))
+        .collect();
+
+    // Assert
+    assert_eq!(
+        documents,
+        vec![
+            Document {
+                title: Some(String::from("Chunked Page")),
+                description: None,
+                text: String::from("Zanzibar spice markets sell cloves."),
+            },
+            Document {
+                title: Some(String::from("Compressed Page")),
+                description: None,
+                text: String::from("Tuvalu coral atolls and lagoons."),
+            },
+            Document::default(),
+            Document {
+                title: Some(String::from("Decompressed Page")),
+                description: None,
+                text: String::from("Nauru phosphate cliffs."),
+            },
+        ]
+    );
+}
+
+/// The bodies larger than the maximum size once decompressed are dropped.
+#[test]
+fn test_extract_decompressed_limit() {
+    let mut encoder = GzEncoder::new(vec![], Compression::best());
+    encoder.write_all(&b"zebra ".repeat(100_000)).unwrap();
+    let mut block =
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Encoding: gzip\r\n\r\n".to_vec();
+    block.extend(encoder.finish().unwrap());
+    let record = WarcRecord {
+        version: String::from("1.1"),
+        headers: vec![],
+        block,
+        offset: RecordOffset::default(),
+    };
+
+    // Act
+    let capped = extract_document(&record, 100_000);
+    let document = extract_document(&record, AnalysisSettings::default().max_body_size);
+
+    // Assert
+    assert_eq!(capped, Document::default());
+    assert!(document.text.starts_with("zebra zebra"));
+    assert_eq!(document.text.len(), 600_000);
+}
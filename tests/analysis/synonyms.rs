@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+use warcse::analysis::{get_analyzer, AnalyzerKind, Synonyms};
+use warcse::configuration::SynonymSettings;
+
+/// The equivalent words expand to each other, the mapped ones to their targets only.
+#[test]
+fn test_synonyms() {
+    let synonyms = Synonyms::parse(
+        "# Vehicles\nCars, automobiles, auto\nfelines, pumas => cat # Big cats\nsports car, racer\n",
+        get_analyzer(AnalyzerKind::English).as_ref(),
+    );
+
+    // Assert
+    assert_eq!(synonyms.get("car"), ["automobil", "auto"]);
+    assert_eq!(synonyms.get("auto"), ["car", "automobil"]);
+    assert_eq!(synonyms.get("felin"), ["cat"]);
+    assert_eq!(synonyms.get("puma"), ["cat"]);
+    assert!(synonyms.get("cat").is_empty());
+    // The rules of several words are ignored.
+    assert!(synonyms.get("racer").is_empty());
+    assert!(synonyms.get("sport").is_empty());
+}
+
+/// The dictionaries are loaded for known analyzers, from existing files.
+#[test]
+fn test_load_synonyms() {
+    let fixture = PathBuf::from(format!(
+        "{}/tests/fixtures/synonyms.txt",
+        env!("CARGO_MANIFEST_DIR")
+    ));
+    let load = |analyzer: &str, path: PathBuf| {
+        let mut settings = SynonymSettings::default();
+        settings.files.insert(String::from(analyzer), path);
+        settings.load().map(|_| settings)
+    };
+
+    // Act
+    let loaded = load("english", fixture.clone()).unwrap();
+
+    // Assert
+    assert_eq!(
+        loaded.dictionaries["english"].get("automobil"),
+        ["car", "motorcar"]
+    );
+    assert!(load("klingon", fixture.clone()).is_err());
+    assert!(load("english", fixture.with_file_name("missing.txt")).is_err());
+}
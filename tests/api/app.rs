@@ -1,37 +1,203 @@
-use sqlx::PgPool;
+use sqlx::{Connection, Executor, PgConnection, PgPool};
 use std::net::TcpListener;
-use warcse::configuration::{get_configuration, DatabaseSettings};
+use std::path::PathBuf;
+use uuid::Uuid;
+use warcse::analysis::AnalyzerKind;
+use warcse::configuration::{get_configuration, AnalysisSettings, DatabaseSettings, Settings};
+use warcse::database::MIGRATOR;
+use warcse::services::{ingest_corpus, load_completions};
 use warcse::startup::run;
 
+/// The corpora loaded in every test database, with their fixture files.
+const FIXTURE_CORPORA: [(&str, &[&str]); 2] = [
+    ("clueweb", &["clueweb09-sample.warc"]),
+    ("crawl", &["crawl-sample.warc.gz", "example-1.1.warc"]),
+];
+
 /// A test application.
 ///
-/// Contains the address to bind to and the connection pool.
+/// Contains the address to bind to and the connection pool of its own database, which is dropped
+/// along with the application.
 pub(crate) struct TestApp {
     pub address: String,
     pub db_pool: PgPool,
+    database_name: String,
+    maintenance_url: String,
+}
+
+/// Implementation for `TestApp` structure.
+impl TestApp {
+    /// Perform a request on /query.
+    ///
+    /// # Arguments
+    ///
+    /// * body      - The JSON body of the request.
+    pub async fn post_query(&self, body: serde_json::Value) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/query", &self.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    /// Index an HTML page into a new corpus, with the English analyzer.
+    ///
+    /// # Arguments
+    ///
+    /// * corpus    - The name of the corpus, also the id of the record (`<urn:uuid:{corpus}>`).
+    /// * uri       - The URI of the page.
+    /// * html      - The page.
+    pub async fn ingest_page(&self, corpus: &str, uri: &str, html: &str) {
+        let block = format!("HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n{}", html);
+        let warc = format!(
+            "WARC/1.0\r\nWARC-Type: response\r\nWARC-Record-ID: <urn:uuid:{}>\r\n\
+             WARC-Target-URI: {}\r\nContent-Length: {}\r\n\r\n{}\r\n\r\n",
+            corpus,
+            uri,
+            block.len(),
+            block
+        );
+        let path = std::env::temp_dir().join(format!("{}.warc", Uuid::new_v4()));
+        std::fs::write(&path, warc).expect("Failed to write the corpus.");
+        ingest_corpus(
+            &self.db_pool,
+            corpus,
+            std::slice::from_ref(&path),
+            false,
+            AnalyzerKind::English,
+            AnalysisSettings::default().max_body_size,
+        )
+        .await
+        .expect("Failed to load the corpus.");
+        std::fs::remove_file(&path).expect("Failed to remove the corpus.");
+    }
+}
+
+/// Drop the test database once the application is no longer used.
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        drop_database(&self.maintenance_url, &self.database_name);
+    }
+}
+
+/// Drop a test database.
+///
+/// # Arguments
+///
+/// * maintenance_url   - The connection string of the `postgres` maintenance database.
+/// * database_name     - The name of the database.
+pub(crate) fn drop_database(maintenance_url: &str, database_name: &str) {
+    let database_name = database_name.to_string();
+    let maintenance_url = maintenance_url.to_string();
+    // The pool may still be used by the server, so the database is force-dropped from a
+    // separate runtime.
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().expect("Failed to create runtime.");
+        runtime.block_on(async {
+            let mut connection = PgConnection::connect(&maintenance_url)
+                .await
+                .expect("Failed to connect to Postgres.");
+            connection
+                .execute(format!(r#"DROP DATABASE "{}" WITH (FORCE)"#, database_name).as_str())
+                .await
+                .expect("Failed to drop database.");
+        });
+    })
+    .join()
+    .expect("Failed to drop database.");
+}
+
+/// Get the path of a fixture file.
+pub(crate) fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(format!(
+        "{}/tests/fixtures/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    ))
 }
 
-/// Spawn an application instance.
+/// Spawn an application instance, over a new database loaded with the fixture corpora.
 pub(crate) async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| {}).await
+}
+
+/// Spawn an application instance with a modified configuration.
+///
+/// # Arguments
+///
+/// * configure - Modify the configuration read from `configuration.yaml`.
+pub(crate) async fn spawn_app_with(configure: fn(&mut Settings)) -> TestApp {
     let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind random port");
     // We retrieve the port assigned to us by the OS
     let port = listener.local_addr().unwrap().port();
     let address = format!("http://127.0.0.1:{}", port);
 
-    let configuration = get_configuration().expect("Failed to read configuration.");
+    let mut configuration = get_configuration().expect("Failed to read configuration.");
+    configure(&mut configuration);
+    configuration.database.database_name = Uuid::new_v4().to_string();
     let connection_pool = configure_database(&configuration.database).await;
 
-    let server = run(listener, connection_pool.clone()).expect("Failed to bind address");
-    let _ = tokio::spawn(server);
+    for (corpus, files) in FIXTURE_CORPORA {
+        let files: Vec<PathBuf> = files.iter().map(|f| fixture(f)).collect();
+        ingest_corpus(
+            &connection_pool,
+            corpus,
+            &files,
+            false,
+            configuration.analysis.analyzer,
+            configuration.analysis.max_body_size,
+        )
+        .await
+        .expect("Failed to load the fixture corpus.");
+    }
+
+    let completions = load_completions(&connection_pool, configuration.search.completion.max_limit)
+        .await
+        .expect("Failed to load the completions.");
+    let server = run(
+        listener,
+        connection_pool.clone(),
+        configuration.search.clone(),
+        configuration.analysis,
+        completions,
+    )
+    .expect("Failed to bind address");
+    tokio::spawn(server);
     TestApp {
         address,
         db_pool: connection_pool,
+        database_name: configuration.database.database_name.clone(),
+        maintenance_url: maintenance_url(&configuration.database),
     }
 }
 
-/// Create a database configuration.
+/// The connection string of the `postgres` maintenance database.
+pub(crate) fn maintenance_url(config: &DatabaseSettings) -> String {
+    format!("{}/postgres", config.connection_string_without_db())
+}
+
+/// Create and migrate a new database.
 pub(crate) async fn configure_database(config: &DatabaseSettings) -> PgPool {
-    // Migrate database
+    let connection_pool = create_database(config).await;
+    MIGRATOR
+        .run(&connection_pool)
+        .await
+        .expect("Failed to migrate the database.");
+    connection_pool
+}
+
+/// Create a new, empty database.
+pub(crate) async fn create_database(config: &DatabaseSettings) -> PgPool {
+    // Create database
+    let mut connection = PgConnection::connect(&maintenance_url(config))
+        .await
+        .expect("Failed to connect to Postgres.");
+    connection
+        .execute(format!(r#"CREATE DATABASE "{}""#, config.database_name).as_str())
+        .await
+        .expect("Failed to create database.");
+
     PgPool::connect(&config.connection_string())
         .await
         .expect("Failed to connect to Postgres.")
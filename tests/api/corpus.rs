@@ -0,0 +1,90 @@
+use crate::app::spawn_app;
+use crate::query::trec_ids;
+use serde_json::{json, Value};
+
+/// Delete a corpus, its records must no longer be returned.
+#[actix_rt::test]
+async fn test_delete_corpus() {
+    let app = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Act
+    let response = client
+        .delete(format!("{}/corpus/1", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert!(response.status().is_success());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["deleted_records"], 5);
+
+    let body: Value = app
+        .post_query(json!({"query": "jaguar"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        trec_ids(&body),
+        vec!["<urn:uuid:0b8f6b0e-0000-4000-8000-000000000002>"]
+    );
+}
+
+/// Delete a corpus that does not exist.
+#[actix_rt::test]
+async fn test_delete_corpus_404() {
+    let app = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Act
+    let response = client
+        .delete(format!("{}/corpus/42", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "not_found");
+    assert_eq!(body["message"], "Corpus 42 not found");
+}
+
+/// The deletions are not exposed to cross-origin requests, unlike the searches.
+#[actix_rt::test]
+async fn test_delete_corpus_cross_origin() {
+    let app = spawn_app().await;
+    let client = reqwest::Client::new();
+    let preflight = |path: &str, method: &str| {
+        client
+            .request(
+                reqwest::Method::OPTIONS,
+                format!("{}/{}", &app.address, path),
+            )
+            .header("Origin", "https://example.org")
+            .header("Access-Control-Request-Method", method)
+            .send()
+    };
+
+    // Act
+    let delete = preflight("corpus/1", "DELETE")
+        .await
+        .expect("Failed to execute request.");
+    let query = preflight("query", "POST")
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert!(!delete.status().is_success());
+    assert!(delete
+        .headers()
+        .get("access-control-allow-methods")
+        .is_none());
+    assert!(query.status().is_success());
+    assert_eq!(
+        query.headers()["access-control-allow-origin"],
+        "https://example.org"
+    );
+}
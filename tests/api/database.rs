@@ -0,0 +1,54 @@
+use crate::app::{create_database, drop_database, fixture, maintenance_url};
+use sqlx::Executor;
+use uuid::Uuid;
+use warcse::configuration::get_configuration;
+use warcse::database::{check_schema, migrate};
+use warcse::services::ingest_corpus;
+
+/// A database created by the former `docker/init.sql` is upgraded, keeping its data.
+#[actix_rt::test]
+async fn test_migrate_legacy_schema() {
+    let mut configuration = get_configuration().expect("Failed to read configuration.");
+    configuration.database.database_name = Uuid::new_v4().to_string();
+    let pool = create_database(&configuration.database).await;
+    let legacy = std::fs::read_to_string(fixture("legacy-init.sql")).unwrap();
+    pool.execute(legacy.as_str()).await.unwrap();
+    pool.execute(
+        r#"
+        INSERT INTO corpus_info (name) VALUES ('legacy');
+        INSERT INTO record_index (trec_id, uri, version, analyzed, corpus_id)
+        VALUES ('legacy-00001', 'http://example.org/', 'WARC/0.18', TRUE, 1);
+        "#,
+    )
+    .await
+    .unwrap();
+
+    // Act
+    let migrated = migrate(&pool).await;
+    let checked = check_schema(&pool).await;
+
+    // Assert
+    assert!(migrated.is_ok(), "{:?}", migrated);
+    assert!(checked.is_ok(), "{:?}", checked);
+    let records: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM record_index")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(records, 1);
+    ingest_corpus(
+        &pool,
+        "clueweb",
+        &[fixture("clueweb09-sample.warc")],
+        false,
+        configuration.analysis.analyzer,
+        configuration.analysis.max_body_size,
+    )
+    .await
+    .expect("Failed to load the corpus.");
+
+    pool.close().await;
+    drop_database(
+        &maintenance_url(&configuration.database),
+        &configuration.database.database_name,
+    );
+}
@@ -0,0 +1,128 @@
+use crate::app::{fixture, spawn_app};
+use serde_json::{json, Value};
+use warcse::analysis::AnalyzerKind;
+use warcse::configuration::AnalysisSettings;
+use warcse::services::{ingest_corpus, update_statistics, STATISTICS_LOCK};
+
+/// Resume a file whose last record was registered but not analyzed, it must be indexed again.
+#[actix_rt::test]
+async fn test_ingest_resume() {
+    let app = spawn_app().await;
+    // The record is left as if the ingestion stopped before its postings were written.
+    for query in [
+        "DELETE FROM word_record_index WHERE record IN (SELECT id FROM record_index WHERE trec_id = 'clueweb09-en0000-00-00002')",
+        "DELETE FROM record_meta WHERE record IN (SELECT id FROM record_index WHERE trec_id = 'clueweb09-en0000-00-00002')",
+        "DELETE FROM record_text WHERE record IN (SELECT id FROM record_index WHERE trec_id = 'clueweb09-en0000-00-00002')",
+        "UPDATE record_index SET analyzed = FALSE, counted = FALSE WHERE trec_id = 'clueweb09-en0000-00-00002'",
+    ] {
+        sqlx::query(query).execute(&app.db_pool).await.unwrap();
+    }
+
+    // Act
+    let summary = ingest_corpus(
+        &app.db_pool,
+        "clueweb",
+        &[fixture("clueweb09-sample.warc")],
+        true,
+        AnalyzerKind::English,
+        AnalysisSettings::default().max_body_size,
+    )
+    .await
+    .expect("Failed to resume the corpus.");
+
+    // Assert
+    assert_eq!(summary.records, 1);
+    assert_eq!(summary.unchanged, 4);
+    let record = sqlx::query!(
+        r#"
+        SELECT COUNT(*)                                                          AS "records!",
+               BOOL_AND(ri.analyzed)                                             AS "analyzed!",
+               (SELECT COUNT(*) FROM word_record_index wri WHERE wri.record = MAX(ri.id)) AS "words!"
+        FROM record_index ri
+        WHERE ri.trec_id = 'clueweb09-en0000-00-00002'
+        "#
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(record.records, 1);
+    assert!(record.analyzed);
+    assert!(record.words > 0);
+}
+
+/// The records of a partly analyzed corpus are only searched once they are counted in the
+/// statistics.
+#[actix_rt::test]
+async fn test_query_partly_analyzed_corpus() {
+    let app = spawn_app().await;
+    // The ingestion stops before the statistics are updated.
+    let interrupted = ingest_corpus(
+        &app.db_pool,
+        "partial",
+        &[fixture("encoded-sample.warc"), fixture("missing.warc")],
+        false,
+        AnalyzerKind::English,
+        AnalysisSettings::default().max_body_size,
+    )
+    .await;
+    assert!(interrupted.is_err());
+
+    // Act
+    let during: Value = app
+        .post_query(json!({"query": "zanzibar", "ranking": "cosine"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    ingest_corpus(
+        &app.db_pool,
+        "partial",
+        &[fixture("encoded-sample.warc")],
+        true,
+        AnalyzerKind::English,
+        AnalysisSettings::default().max_body_size,
+    )
+    .await
+    .expect("Failed to resume the corpus.");
+    let after: Value = app
+        .post_query(json!({"query": "zanzibar", "ranking": "cosine"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(during["total_hits"], 0);
+    assert_eq!(after["total_hits"], 1);
+    let result = &after["result"][0];
+    assert_eq!(
+        result["trec_id"],
+        "<urn:uuid:5c3fdb2a-2222-4222-8222-000000000001>"
+    );
+    assert!(result["score_mixed"].as_f64().unwrap().is_finite());
+}
+
+/// The statistics lock is held until the end of the transaction updating the statistics.
+#[actix_rt::test]
+async fn test_statistics_lock() {
+    let app = spawn_app().await;
+    let mut tx = app.db_pool.begin().await.unwrap();
+
+    // Act
+    update_statistics(&mut tx).await.unwrap();
+    let during: bool = sqlx::query_scalar("SELECT pg_try_advisory_xact_lock($1)")
+        .bind(STATISTICS_LOCK)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    let after: bool = sqlx::query_scalar("SELECT pg_try_advisory_xact_lock($1)")
+        .bind(STATISTICS_LOCK)
+        .fetch_one(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(!during);
+    assert!(after);
+}
@@ -1,3 +1,8 @@
 mod app;
+mod corpus;
+mod database;
+mod ingest;
 mod query;
+mod record;
 mod status;
+mod suggest;
@@ -1,4 +1,10 @@
-use crate::app::spawn_app;
+use crate::app::{fixture, spawn_app, spawn_app_with};
+use crate::record::ingest_encoded;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use warcse::analysis::AnalyzerKind;
+use warcse::configuration::{AnalysisSettings, StopwordMode};
+use warcse::services::ingest_corpus;
 
 #[actix_rt::test]
 async fn test_query_404() {
@@ -8,7 +14,7 @@ async fn test_query_404() {
 
     // Act
     let response = client
-        .post(&format!("{}/imaginaryword", &app.address))
+        .post(format!("{}/imaginaryword", &app.address))
         .header("Content-Type", "application/json")
         .body(data)
         .send()
@@ -17,4 +23,1149 @@ async fn test_query_404() {
 
     // Assert
     assert_eq!(response.status().as_u16(), 404);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "not_found");
+}
+
+/// Get the TREC ids of the results, in rank order.
+pub(crate) fn trec_ids(body: &Value) -> Vec<&str> {
+    body["result"]
+        .as_array()
+        .expect("Missing result array.")
+        .iter()
+        .map(|r| r["trec_id"].as_str().unwrap())
+        .collect()
+}
+
+/// Records containing more of the query terms rank higher.
+#[actix_rt::test]
+async fn test_query_ranking() {
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.post_query(json!({"query": "jaguar car"})).await;
+
+    // Assert
+    assert!(response.status().is_success());
+    let body: Value = response.json().await.unwrap();
+    let ids = trec_ids(&body);
+    // "cars" is stemmed like "car".
+    assert_eq!(body["result_count"], 5);
+    let both = ids.iter().position(|id| *id == "clueweb09-en0000-00-00001");
+    let one = ids.iter().position(|id| *id == "clueweb09-en0000-00-00002");
+    assert!(both.unwrap() < one.unwrap());
+}
+
+/// A query on a word that is not indexed returns an empty result.
+#[actix_rt::test]
+async fn test_query_unknown_word() {
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.post_query(json!({"query": "xylophone"})).await;
+
+    // Assert
+    assert!(response.status().is_success());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["result_count"], 0);
+}
+
+/// With BM25, records with more occurrences of the term, relative to their length, rank higher.
+#[actix_rt::test]
+async fn test_query_bm25_ranking() {
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_query(json!({"query": "jaguar", "ranking": "bm25"}))
+        .await;
+
+    // Assert
+    assert!(response.status().is_success());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(
+        trec_ids(&body),
+        vec![
+            "clueweb09-en0000-00-00001",
+            "clueweb09-en0000-00-00002",
+            "<urn:uuid:0b8f6b0e-0000-4000-8000-000000000002>"
+        ]
+    );
+    let scores: Vec<f64> = body["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["score_mixed"].as_f64().unwrap())
+        .collect();
+    assert!(scores.windows(2).all(|w| w[0] > w[1]));
+}
+
+/// The ranking model can be selected per request.
+#[actix_rt::test]
+async fn test_query_cosine_ranking() {
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_query(json!({"query": "jaguar car", "ranking": "cosine"}))
+        .await;
+
+    // Assert
+    assert!(response.status().is_success());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["result_count"], 5);
+    assert!(body["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|r| r["corpus_score"].as_f64().unwrap() > 0_f64));
+}
+
+/// An unknown ranking model is rejected.
+#[actix_rt::test]
+async fn test_query_unknown_ranking() {
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_query(json!({"query": "jaguar", "ranking": "pagerank"}))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "bad_request");
+}
+
+/// The record-only cosine model ignores the corpus score.
+#[actix_rt::test]
+async fn test_query_record_cosine_ranking() {
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_query(json!({"query": "jaguar car", "ranking": "record_cosine"}))
+        .await;
+
+    // Assert
+    assert!(response.status().is_success());
+    let body: Value = response.json().await.unwrap();
+    let results = body["result"].as_array().unwrap();
+    assert_eq!(results.len(), 5);
+    assert!(results
+        .iter()
+        .all(|r| r["corpus_score"] == 0_f64 && r["score_mixed"] == r["record_score"]));
+}
+
+/// Results are paginated with offset/limit, reporting the total number of hits.
+#[actix_rt::test]
+async fn test_query_pagination() {
+    let app = spawn_app().await;
+    let all: Value = app
+        .post_query(json!({"query": "jaguar car"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Act
+    let response = app
+        .post_query(json!({"query": "jaguar car", "offset": 1, "limit": 2}))
+        .await;
+
+    // Assert
+    assert!(response.status().is_success());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["result_count"], 2);
+    assert_eq!(body["total_hits"], 5);
+    assert_eq!(trec_ids(&body), trec_ids(&all)[1..3].to_vec());
+}
+
+/// Following the cursors returns every result once, in rank order.
+#[actix_rt::test]
+async fn test_query_cursor() {
+    let app = spawn_app().await;
+    let all: Value = app
+        .post_query(json!({"query": "jaguar car"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Act
+    let first: Value = app
+        .post_query(json!({"query": "jaguar car", "limit": 3}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let cursor = first["next_cursor"].as_str().expect("Missing cursor.");
+    let second: Value = app
+        .post_query(json!({"query": "jaguar car", "limit": 3, "cursor": cursor}))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(second["next_cursor"].is_null());
+    let mut ids = trec_ids(&first);
+    ids.extend(trec_ids(&second));
+    assert_eq!(ids, trec_ids(&all));
+}
+
+/// An invalid cursor is rejected.
+#[actix_rt::test]
+async fn test_query_invalid_cursor() {
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_query(json!({"query": "jaguar", "cursor": "not-a-cursor"}))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+/// The query words are case insensitive.
+#[actix_rt::test]
+async fn test_query_case_insensitive() {
+    let app = spawn_app().await;
+    let expected: Value = app
+        .post_query(json!({"query": "jaguar car"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Act
+    let body: Value = app
+        .post_query(json!({"query": "Jaguar CAR"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(trec_ids(&body), trec_ids(&expected));
+    assert_eq!(body["result"], expected["result"]);
+}
+
+/// The queries fail with a JSON error when the database can not be reached.
+#[actix_rt::test]
+async fn test_query_database_unavailable() {
+    let app = spawn_app().await;
+    app.db_pool.close().await;
+
+    // Act
+    let response = app.post_query(json!({"query": "jaguar"})).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 503);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "database_unavailable");
+    assert_eq!(body["message"], "The database is unavailable");
+}
+
+/// Perform a query and get the TREC ids of the results, sorted.
+async fn matching(app: &crate::app::TestApp, query: &str) -> Vec<String> {
+    let body: Value = app
+        .post_query(json!({"query": query}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let mut ids: Vec<String> = trec_ids(&body).into_iter().map(String::from).collect();
+    ids.sort();
+    ids
+}
+
+/// Excluded terms remove the records containing them.
+#[actix_rt::test]
+async fn test_query_boolean_exclusion() {
+    let app = spawn_app().await;
+
+    // Act
+    let excluded = matching(&app, "jaguar -car").await;
+    let not = matching(&app, "jaguar NOT car").await;
+
+    // Assert
+    assert_eq!(
+        excluded,
+        [
+            "<urn:uuid:0b8f6b0e-0000-4000-8000-000000000002>",
+            "clueweb09-en0000-00-00002"
+        ]
+    );
+    assert_eq!(not, excluded);
+}
+
+/// AND, OR, NOT and parentheses.
+#[actix_rt::test]
+async fn test_query_boolean_operators() {
+    let app = spawn_app().await;
+
+    // Act
+    let and = matching(&app, "jaguar AND car").await;
+    let grouped = matching(&app, "(jaguar OR york) AND NOT new").await;
+
+    // Assert
+    assert_eq!(and, ["clueweb09-en0000-00-00001"]);
+    assert_eq!(
+        grouped,
+        [
+            "<urn:uuid:0b8f6b0e-0000-4000-8000-000000000002>",
+            "clueweb09-en0000-00-00002"
+        ]
+    );
+}
+
+/// Required terms must match, the optional ones only count for the score.
+#[actix_rt::test]
+async fn test_query_boolean_required() {
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_query(json!({"query": "+new york", "ranking": "bm25"}))
+        .await;
+
+    // Assert
+    let body: Value = response.json().await.unwrap();
+    let ids = trec_ids(&body);
+    assert_eq!(ids.len(), 4);
+    assert!(ids[..2].contains(&"clueweb09-en0000-00-00003"));
+    assert!(ids[..2].contains(&"clueweb09-en0000-00-00004"));
+}
+
+/// Queries made only of excluded terms match nothing.
+#[actix_rt::test]
+async fn test_query_boolean_only_excluded() {
+    let app = spawn_app().await;
+
+    // Act
+    let ids = matching(&app, "-car").await;
+
+    // Assert
+    assert!(ids.is_empty());
+}
+
+/// Malformed queries are rejected.
+#[actix_rt::test]
+async fn test_query_boolean_syntax_error() {
+    let app = spawn_app().await;
+
+    for query in ["(jaguar car", "jaguar)", "jaguar AND", "OR jaguar"] {
+        // Act
+        let response = app.post_query(json!({ "query": query })).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 400, "{}", query);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["code"], "bad_request");
+    }
+}
+
+/// Deeply nested queries are rejected, instead of overflowing the stack.
+#[actix_rt::test]
+async fn test_query_too_deep() {
+    let app = spawn_app().await;
+    let nested = |depth: usize| format!("{}jaguar{}", "(".repeat(depth), ")".repeat(depth));
+
+    for query in [
+        format!("{}jaguar", "-".repeat(1000)),
+        format!("{}jaguar", "+NOT ".repeat(500)),
+        nested(1000),
+        format!("{}jaguar", "(".repeat(1000)),
+    ] {
+        // Act
+        let response = app.post_query(json!({ "query": query })).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 400);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["code"], "bad_request");
+    }
+    assert_eq!(
+        matching(&app, &nested(10)).await,
+        matching(&app, "jaguar").await
+    );
+}
+
+/// Quoted phrases, and words split by the tokenizer, match consecutive terms.
+#[actix_rt::test]
+async fn test_query_phrase() {
+    let app = spawn_app().await;
+
+    // Act
+    let quoted = matching(&app, "\"new york\"").await;
+    let split = matching(&app, "new-york").await;
+    let reversed = matching(&app, "\"york new\"").await;
+
+    // Assert
+    assert_eq!(quoted, ["clueweb09-en0000-00-00003"]);
+    assert_eq!(split, quoted);
+    assert!(reversed.is_empty());
+}
+
+/// NEAR/n matches terms at most n words apart.
+#[actix_rt::test]
+async fn test_query_near() {
+    let app = spawn_app().await;
+
+    // Act
+    let near3 = matching(&app, "new NEAR/3 york").await;
+    let near4 = matching(&app, "york NEAR/4 new").await;
+
+    // Assert
+    assert_eq!(near3, ["clueweb09-en0000-00-00003"]);
+    assert_eq!(
+        near4,
+        ["clueweb09-en0000-00-00003", "clueweb09-en0000-00-00004"]
+    );
+}
+
+/// The proximity boost is added to the records with close query words.
+#[actix_rt::test]
+async fn test_query_proximity_boost() {
+    let app = spawn_app().await;
+    let boosted = spawn_app_with(|c| c.search.proximity_boost = 100_f64).await;
+    let query = json!({"query": "jaguar car", "ranking": "bm25"});
+
+    // Act
+    let body: Value = app.post_query(query.clone()).await.json().await.unwrap();
+    let boosted: Value = boosted.post_query(query).await.json().await.unwrap();
+
+    // Assert
+    let score = |body: &Value, trec_id: &str| {
+        body["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["trec_id"] == trec_id)
+            .map(|r| r["score_mixed"].as_f64().unwrap())
+            .unwrap()
+    };
+    // "Jaguar Cars": the words are adjacent once stemmed.
+    let boost =
+        score(&boosted, "clueweb09-en0000-00-00001") - score(&body, "clueweb09-en0000-00-00001");
+    assert!((boost - 100_f64).abs() < 1e-9);
+    // Records with a single query word are not boosted.
+    assert_eq!(
+        score(&boosted, "clueweb09-en0000-00-00002"),
+        score(&body, "clueweb09-en0000-00-00002")
+    );
+}
+
+/// site: restricts the results to a host and its subdomains.
+#[actix_rt::test]
+async fn test_query_field_site() {
+    let app = spawn_app().await;
+
+    // Act
+    let org = matching(&app, "jaguar site:example.org").await;
+    let com = matching(&app, "jaguar site:Example.com").await;
+    let only = matching(&app, "site:www.example.org").await;
+
+    // Assert
+    assert_eq!(org, ["clueweb09-en0000-00-00001"]);
+    assert_eq!(
+        com,
+        [
+            "<urn:uuid:0b8f6b0e-0000-4000-8000-000000000002>",
+            "clueweb09-en0000-00-00002"
+        ]
+    );
+    assert_eq!(
+        only,
+        [
+            "<urn:uuid:5c3fdb2a-1111-4111-8111-111111111111>",
+            "clueweb09-en0000-00-00001",
+            "clueweb09-en0000-00-00003",
+            "clueweb09-en0000-00-00005"
+        ]
+    );
+}
+
+/// inurl: and corpus: filters, which may be excluded as well.
+#[actix_rt::test]
+async fn test_query_field_url_and_corpus() {
+    let app = spawn_app().await;
+
+    // Act
+    let inurl = matching(&app, "inurl:login").await;
+    let corpus = matching(&app, "jaguar corpus:crawl").await;
+    let excluded = matching(&app, "jaguar -corpus:clueweb").await;
+    let either = matching(&app, "inurl:login OR inurl:york").await;
+    let literal = matching(&app, "inurl:big-cats").await;
+    let wildcards = matching(&app, "inurl:big_cats OR inurl:% OR meta:%").await;
+
+    // Assert
+    assert_eq!(inurl, ["clueweb09-en0000-00-00005"]);
+    assert_eq!(literal, ["clueweb09-en0000-00-00002"]);
+    // The LIKE wildcards are matched literally.
+    assert!(wildcards.is_empty());
+    assert_eq!(corpus, ["<urn:uuid:0b8f6b0e-0000-4000-8000-000000000002>"]);
+    assert_eq!(excluded, corpus);
+    assert_eq!(
+        either,
+        [
+            "clueweb09-en0000-00-00003",
+            "clueweb09-en0000-00-00004",
+            "clueweb09-en0000-00-00005"
+        ]
+    );
+}
+
+/// meta: searches the metadata of the records.
+#[actix_rt::test]
+async fn test_query_field_meta() {
+    let app = spawn_app().await;
+    sqlx::query(
+        "INSERT INTO record_meta (record, meta)
+         SELECT id, 'Big cats of the Americas' FROM record_index
+         WHERE trec_id = 'clueweb09-en0000-00-00002'",
+    )
+    .execute(&app.db_pool)
+    .await
+    .expect("Failed to insert the metadata.");
+
+    // Act
+    let word = matching(&app, "jaguar meta:americas").await;
+    let quoted = matching(&app, "meta:\"BIG CATS\"").await;
+
+    // Assert
+    assert_eq!(word, ["clueweb09-en0000-00-00002"]);
+    assert_eq!(quoted, word);
+}
+
+/// The query words are stemmed like the indexed text.
+#[actix_rt::test]
+async fn test_query_stemming() {
+    let app = spawn_app().await;
+
+    // Act
+    let plural = matching(&app, "JAGUARS").await;
+    let verb = matching(&app, "hunting").await;
+
+    // Assert
+    assert_eq!(plural, matching(&app, "jaguar").await);
+    assert_eq!(plural.len(), 3);
+    assert_eq!(verb, ["clueweb09-en0000-00-00002"]);
+}
+
+/// The query words are analyzed with the analyzer of each corpus.
+#[actix_rt::test]
+async fn test_query_corpus_analyzer() {
+    let app = spawn_app().await;
+    let block = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n<p>Jaguars of Belize</p>";
+    let warc = format!(
+        "WARC/1.0\r\nWARC-Type: response\r\nWARC-Record-ID: <urn:uuid:legacy>\r\n\
+         WARC-Target-URI: http://legacy.example.net/\r\nContent-Length: {}\r\n\r\n{}\r\n\r\n",
+        block.len(),
+        block
+    );
+    let path = std::env::temp_dir().join(format!("{}.warc", uuid::Uuid::new_v4()));
+    std::fs::write(&path, warc).expect("Failed to write the corpus.");
+    ingest_corpus(
+        &app.db_pool,
+        "legacy",
+        std::slice::from_ref(&path),
+        false,
+        AnalyzerKind::Simple,
+        AnalysisSettings::default().max_body_size,
+    )
+    .await
+    .expect("Failed to load the corpus.");
+    std::fs::remove_file(&path).expect("Failed to remove the corpus.");
+
+    // Act
+    let stemmed = matching(&app, "jaguar").await;
+    let plural = matching(&app, "jaguars").await;
+
+    // Assert
+    assert!(!stemmed.contains(&String::from("<urn:uuid:legacy>")));
+    assert!(plural.contains(&String::from("<urn:uuid:legacy>")));
+    assert_eq!(plural.len(), stemmed.len() + 1);
+}
+
+/// Query the API, returning the response body.
+async fn search(app: &crate::app::TestApp, query: &str) -> Value {
+    app.post_query(json!({"query": query}))
+        .await
+        .json()
+        .await
+        .unwrap()
+}
+
+/// The stopwords are removed from the query, and listed in the response.
+#[actix_rt::test]
+async fn test_query_dropped_terms() {
+    let app = spawn_app_with(|c| c.search.stopwords.threshold = 0.7).await;
+
+    // Act
+    // "the" is listed, "a" appears in 6 of the 8 records.
+    let body = search(&app, "the jaguar a").await;
+
+    // Assert
+    assert_eq!(body["dropped_terms"], json!(["a", "the"]));
+    assert_eq!(body["total_hits"], 3);
+    assert_eq!(search(&app, "jaguar").await["dropped_terms"], json!([]));
+}
+
+/// The stopword threshold is configurable.
+#[actix_rt::test]
+async fn test_query_stopword_threshold() {
+    let app = spawn_app_with(|c| {
+        c.search.stopwords.threshold = 0.7;
+        c.search.stopwords.lists.clear();
+    })
+    .await;
+
+    // Act
+    // "a" appears in 6 of the 8 records.
+    let body = search(&app, "jaguar a").await;
+
+    // Assert
+    assert_eq!(body["dropped_terms"], json!(["a"]));
+    assert_eq!(body["total_hits"], 3);
+}
+
+/// A query made of stopwords only is not emptied.
+#[actix_rt::test]
+async fn test_query_only_stopwords() {
+    let app = spawn_app().await;
+
+    // Act
+    let body = search(&app, "the a").await;
+
+    // Assert
+    assert_eq!(body["dropped_terms"], json!([]));
+    assert_eq!(body["total_hits"], 7);
+}
+
+/// The stopwords of phrases are kept, or removed depending on the stopword mode.
+#[actix_rt::test]
+async fn test_query_stopword_mode() {
+    let app = spawn_app().await;
+    let remove = spawn_app_with(|c| c.search.stopwords.mode = StopwordMode::Remove).await;
+
+    // Act
+    let kept = search(&app, "\"city of england\"").await;
+    let removed = search(&remove, "\"city of england\"").await;
+
+    // Assert
+    assert_eq!(kept["dropped_terms"], json!([]));
+    assert_eq!(kept["total_hits"], 0);
+    // "city" and "england" must still appear within the length of the phrase.
+    assert_eq!(removed["dropped_terms"], json!(["of"]));
+    assert_eq!(trec_ids(&removed), ["clueweb09-en0000-00-00004"]);
+    assert_eq!(
+        matching(&app, "\"city in england\"").await,
+        trec_ids(&removed)
+    );
+}
+
+/// The results include the best matching part of the record, with the query terms highlighted.
+#[actix_rt::test]
+async fn test_query_snippet() {
+    let app = spawn_app().await;
+
+    // Act
+    let body = search(&app, "museum").await;
+
+    // Assert
+    let snippet = body["result"][0]["snippet"].as_str().unwrap();
+    assert!(snippet.contains("A new <b>museum</b> opened in York"));
+    // The page is short, it is not cut.
+    assert!(!snippet.starts_with("… "));
+}
+
+/// The snippet markers are configurable, and highlight the words matching the analyzed terms.
+#[actix_rt::test]
+async fn test_query_snippet_markers() {
+    let app = spawn_app_with(|c| {
+        c.search.snippets.pre_tag = String::from("[");
+        c.search.snippets.post_tag = String::from("]");
+    })
+    .await;
+    let disabled = spawn_app_with(|c| c.search.snippets.length = 0).await;
+
+    // Act
+    let body = search(&app, "hunting").await;
+
+    // Assert
+    let snippet = body["result"][0]["snippet"].as_str().unwrap();
+    assert!(snippet.contains("The jaguar [hunts] in"), "{}", snippet);
+    assert!(search(&disabled, "hunting").await["result"][0]["snippet"].is_null());
+}
+
+/// The text of the snippets is HTML escaped, so the markup shown by a page stays text.
+#[actix_rt::test]
+async fn test_query_snippet_escaped() {
+    let app = spawn_app().await;
+    let block = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n\
+                 <p>Zebra tip: &lt;img src=x onerror=\"alert(1)\"&gt; &amp; more</p>";
+    let warc = format!(
+        "WARC/1.0\r\nWARC-Type: response\r\nWARC-Record-ID: <urn:uuid:markup>\r\n\
+         WARC-Target-URI: http://markup.example.net/\r\nContent-Length: {}\r\n\r\n{}\r\n\r\n",
+        block.len(),
+        block
+    );
+    let path = std::env::temp_dir().join(format!("{}.warc", uuid::Uuid::new_v4()));
+    std::fs::write(&path, warc).expect("Failed to write the corpus.");
+    ingest_corpus(
+        &app.db_pool,
+        "markup",
+        std::slice::from_ref(&path),
+        false,
+        AnalyzerKind::English,
+        AnalysisSettings::default().max_body_size,
+    )
+    .await
+    .expect("Failed to load the corpus.");
+    std::fs::remove_file(&path).expect("Failed to remove the corpus.");
+
+    // Act
+    let body = search(&app, "zebra").await;
+
+    // Assert
+    assert_eq!(
+        body["result"][0]["snippet"],
+        "<b>Zebra</b> tip: &lt;img src=x onerror=&quot;alert(1)&quot;&gt; &amp; more"
+    );
+}
+
+/// The pages are decoded with their declared charset.
+#[actix_rt::test]
+async fn test_query_charset() {
+    let app = spawn_app().await;
+
+    // Act
+    let accented = matching(&app, "\"café in são paulo\"").await;
+    let folded = matching(&app, "sao paulo").await;
+
+    // Assert
+    assert_eq!(
+        accented,
+        ["<urn:uuid:0b8f6b0e-0000-4000-8000-000000000002>"]
+    );
+    assert_eq!(folded, accented);
+}
+
+/// The chunked and compressed bodies are indexed decoded.
+#[actix_rt::test]
+async fn test_query_encoded_bodies() {
+    let app = spawn_app().await;
+    ingest_encoded(&app).await;
+
+    // Act
+    let chunked = search(&app, "zanzibar").await;
+    let gzip = matching(&app, "tuvalu").await;
+
+    // Assert
+    assert_eq!(
+        trec_ids(&chunked),
+        ["<urn:uuid:5c3fdb2a-2222-4222-8222-000000000001>"]
+    );
+    assert_eq!(
+        chunked["result"][0]["snippet"],
+        "<b>Zanzibar</b> spice markets sell cloves"
+    );
+    // The brotli body also contains "tuvalu", but its encoding is not supported.
+    assert_eq!(gzip, ["<urn:uuid:5c3fdb2a-2222-4222-8222-000000000002>"]);
+}
+
+/// The markup, scripts, navigation, headers and footers of the pages are not indexed.
+#[actix_rt::test]
+async fn test_query_boilerplate() {
+    let app = spawn_app().await;
+
+    // Act & Assert
+    for query in ["dealers", "menu", "copyright", "var", "html", "body"] {
+        assert!(matching(&app, query).await.is_empty(), "{}", query);
+    }
+}
+
+/// The title and the meta description of the pages are stored in the metadata.
+#[actix_rt::test]
+async fn test_query_page_metadata() {
+    let app = spawn_app().await;
+
+    // Act
+    let meta = sqlx::query!(
+        "SELECT rm.name, rm.meta FROM record_meta rm
+         JOIN record_index ri ON ri.id = rm.record
+         WHERE ri.trec_id = 'clueweb09-en0000-00-00001'
+         ORDER BY rm.name"
+    )
+    .fetch_all(&app.db_pool)
+    .await
+    .unwrap();
+    let title = matching(&app, "meta:\"rainforest diary\"").await;
+
+    // Assert
+    let meta: Vec<(Option<String>, String)> = meta.into_iter().map(|m| (m.name, m.meta)).collect();
+    assert_eq!(
+        meta,
+        [
+            (
+                Some(String::from("description")),
+                String::from("Luxury cars built in England")
+            ),
+            (Some(String::from("title")), String::from("Jaguar Cars")),
+        ]
+    );
+    assert_eq!(title, ["<urn:uuid:0b8f6b0e-0000-4000-8000-000000000002>"]);
+}
+
+/// The unknown query words get the close indexed words as suggestions.
+#[actix_rt::test]
+async fn test_query_suggestions() {
+    let app = spawn_app().await;
+
+    // Act
+    let misspelled = search(&app, "Jaguer musem").await;
+    let known = search(&app, "jaguar museum").await;
+
+    // Assert
+    assert_eq!(misspelled["total_hits"], 0);
+    let suggestions = misspelled["suggestions"].as_array().unwrap();
+    assert_eq!(suggestions.len(), 2);
+    assert_eq!(suggestions[0]["term"], "jaguer");
+    assert_eq!(suggestions[0]["suggestions"][0], "jaguar");
+    assert_eq!(suggestions[1]["term"], "musem");
+    assert_eq!(suggestions[1]["suggestions"][0], "museum");
+    assert_eq!(misspelled["did_you_mean"], "jaguar museum");
+    assert_eq!(known["suggestions"], json!([]));
+    assert_eq!(known["did_you_mean"], Value::Null);
+}
+
+/// The suggestions of the unknown words are searched when asked to, with a lower weight.
+#[actix_rt::test]
+async fn test_query_expand_suggestions() {
+    let app = spawn_app().await;
+    let expanding = spawn_app_with(|c| c.search.spelling.expand = true).await;
+
+    // Act
+    let score = |body: &Value| body["result"][0]["score_mixed"].as_f64().unwrap();
+    let exact: Value = app
+        .post_query(json!({"query": "jaguar", "ranking": "bm25"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let expanded: Value = app
+        .post_query(json!({"query": "jaguer", "ranking": "bm25", "expand": true}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let configured = search(&expanding, "jaguer").await;
+    let disabled: Value = expanding
+        .post_query(json!({"query": "jaguer", "expand": false}))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(trec_ids(&expanded), trec_ids(&exact));
+    assert!((score(&expanded) - 0.5 * score(&exact)).abs() < 1e-9);
+    assert_eq!(configured["total_hits"], 3);
+    assert_eq!(disabled["total_hits"], 0);
+}
+
+/// The query terms are expanded with their synonyms, with a lower weight.
+#[actix_rt::test]
+async fn test_query_synonyms() {
+    let app = spawn_app_with(|c| {
+        c.search
+            .synonyms
+            .files
+            .insert(String::from("english"), fixture("synonyms.txt"));
+    })
+    .await;
+    let plain = spawn_app().await;
+
+    // Act
+    let bm25 = |query: &str| json!({"query": query, "ranking": "bm25"});
+    let score = |body: &Value| body["result"][0]["score_mixed"].as_f64().unwrap();
+    let automobile: Value = app
+        .post_query(bm25("automobiles"))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let car: Value = app.post_query(bm25("car")).await.json().await.unwrap();
+    let feline = matching(&app, "feline").await;
+    let cat = matching(&app, "cat").await;
+    let without = search(&plain, "automobiles").await;
+
+    // Assert
+    assert_eq!(trec_ids(&automobile), trec_ids(&car));
+    assert!((score(&automobile) - 0.5 * score(&car)).abs() < 1e-9);
+    assert_eq!(
+        automobile["expansions"],
+        json!([
+            {"term": "car", "weight": 0.5, "source": "synonym"},
+            {"term": "motorcar", "weight": 0.5, "source": "synonym"}
+        ])
+    );
+    assert_eq!(feline, cat);
+    // The mapping only goes one way.
+    assert_eq!(search(&app, "cat").await["expansions"], json!([]));
+    assert_eq!(without["total_hits"], 0);
+}
+
+/// With pseudo-relevance feedback, the terms of the best results are added to the query.
+#[actix_rt::test]
+async fn test_query_feedback() {
+    let app = spawn_app().await;
+
+    // Act
+    let plain = search(&app, "museum").await;
+    let body: Value = app
+        .post_query(json!({"query": "museum", "feedback": true}))
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(plain["total_hits"], 1);
+    assert_eq!(plain["expansions"], json!([]));
+    assert_eq!(trec_ids(&body)[0], "clueweb09-en0000-00-00004");
+    assert!(body["total_hits"].as_u64().unwrap() > 1);
+    let expansions = body["expansions"].as_array().unwrap();
+    assert_eq!(expansions.len(), 5);
+    assert_eq!(expansions[0]["weight"], 0.5);
+    for expansion in expansions {
+        assert_eq!(expansion["source"], "feedback");
+        assert!(expansion["weight"].as_f64().unwrap() <= 0.5);
+        assert!(!["museum", "a", "in", "is"].contains(&expansion["term"].as_str().unwrap()));
+    }
+}
+
+/// Get the BM25 score of each result of a query.
+async fn bm25_scores(app: &crate::app::TestApp, mut request: Value) -> HashMap<String, f64> {
+    request["ranking"] = json!("bm25");
+    let body: Value = app.post_query(request).await.json().await.unwrap();
+    body["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["trec_id"].as_str().unwrap().to_string(),
+                r["score_mixed"].as_f64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+/// `clause^n` multiplies the weight of the terms of a clause.
+#[actix_rt::test]
+async fn test_query_term_boost() {
+    let app = spawn_app().await;
+
+    // Act
+    let plain = bm25_scores(&app, json!({"query": "museum"})).await;
+    let boosted = bm25_scores(&app, json!({"query": "museum^2.5"})).await;
+    let group = bm25_scores(&app, json!({"query": "(museum city)^2"})).await;
+    let unboosted_group = bm25_scores(&app, json!({"query": "museum city"})).await;
+    let mixed = bm25_scores(&app, json!({"query": "museum^3 city"})).await;
+    let city = bm25_scores(&app, json!({"query": "city"})).await;
+
+    // Assert
+    let id = "clueweb09-en0000-00-00004";
+    assert!((boosted[id] - 2.5 * plain[id]).abs() < 1e-9);
+    assert!((group[id] - 2_f64 * unboosted_group[id]).abs() < 1e-9);
+    assert!((mixed[id] - (3_f64 * plain[id] + city[id])).abs() < 1e-9);
+    assert_eq!(group.len(), unboosted_group.len());
+}
+
+/// The boosts must be positive numbers.
+#[actix_rt::test]
+async fn test_query_invalid_boost() {
+    let app = spawn_app().await;
+
+    for query in ["jaguar^", "jaguar^abc", "jaguar^0", "jaguar^-2", "^2"] {
+        // Act
+        let response = app.post_query(json!({ "query": query })).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 400, "{}", query);
+    }
+    let response = app
+        .post_query(json!({"query": "jaguar", "boosts": {"title": 0}}))
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+/// The words found in the title or URL of a record weigh more with the field boosts.
+#[actix_rt::test]
+async fn test_query_field_boosts() {
+    let app = spawn_app().await;
+
+    // Act
+    let plain = bm25_scores(&app, json!({"query": "rainforest"})).await;
+    let title = bm25_scores(&app, json!({"query": "rainforest", "boosts": {"title": 3}})).await;
+    let both = bm25_scores(
+        &app,
+        json!({"query": "rainforest", "boosts": {"title": 3, "url": 2}}),
+    )
+    .await;
+
+    // Assert
+    // "Rainforest Diary", at http://blog.example.com/rainforest.
+    let diary = "<urn:uuid:0b8f6b0e-0000-4000-8000-000000000002>";
+    // "The Jaguar", at http://animals.example.com/big-cats/jaguar.
+    let jaguar = "clueweb09-en0000-00-00002";
+    assert!((title[diary] - 3_f64 * plain[diary]).abs() < 1e-9);
+    assert!((both[diary] - 6_f64 * plain[diary]).abs() < 1e-9);
+    assert!((both[jaguar] - plain[jaguar]).abs() < 1e-9);
+}
+
+/// The field boosts compare the analyzed words of the title and URL, not their text: the stemmed
+/// words match, and the words only contained in another one do not.
+#[actix_rt::test]
+async fn test_query_field_boosts_analyzed() {
+    let app = spawn_app().await;
+    app.ingest_page(
+        "boosts",
+        "http://oscar.example.net/scars",
+        "<html><head><title>Happy Cities</title></head>\
+         <body><p>A happy car tour of the cities.</p></body></html>",
+    )
+    .await;
+    let page = "<urn:uuid:boosts>";
+    let boosts = json!({"title": 3, "url": 2});
+
+    // Act
+    let happy = bm25_scores(&app, json!({"query": "happy"})).await;
+    let happy_boosted = bm25_scores(&app, json!({"query": "happy", "boosts": boosts})).await;
+    let city = bm25_scores(&app, json!({"query": "city"})).await;
+    let city_boosted = bm25_scores(&app, json!({"query": "city", "boosts": boosts})).await;
+    let car = bm25_scores(&app, json!({"query": "car"})).await;
+    let car_boosted = bm25_scores(&app, json!({"query": "car", "boosts": boosts})).await;
+
+    // Assert
+    assert!((happy_boosted[page] - 3_f64 * happy[page]).abs() < 1e-9);
+    assert!((city_boosted[page] - 3_f64 * city[page]).abs() < 1e-9);
+    assert!((car_boosted[page] - car[page]).abs() < 1e-9);
+}
+
+/// Find the first node of an explanation whose description starts with a prefix.
+fn explanation_node<'a>(explanation: &'a Value, prefix: &str) -> Option<&'a Value> {
+    if explanation["description"]
+        .as_str()
+        .unwrap()
+        .starts_with(prefix)
+    {
+        return Some(explanation);
+    }
+    explanation["details"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find_map(|detail| explanation_node(detail, prefix))
+}
+
+/// `explain` adds the explanation of the score of each result.
+#[actix_rt::test]
+async fn test_query_explain() {
+    let app = spawn_app().await;
+
+    for (ranking, root) in [
+        (
+            "cosine",
+            "score_mixed, (1 - record_score) * log2(corpus_score)",
+        ),
+        ("record_cosine", "record_score, numerator / "),
+        ("bm25", "score, sum of:"),
+    ] {
+        // Act
+        let request = json!({"query": "jaguar cars", "ranking": ranking, "explain": true});
+        let body: Value = app.post_query(request).await.json().await.unwrap();
+
+        // Assert
+        let results = body["result"].as_array().unwrap();
+        assert!(!results.is_empty());
+        for result in results {
+            let explanation = &result["explanation"];
+            assert!(
+                explanation["description"]
+                    .as_str()
+                    .unwrap()
+                    .starts_with(root),
+                "{}",
+                ranking
+            );
+            let value = explanation["value"].as_f64().unwrap();
+            let score = result["score_mixed"].as_f64().unwrap();
+            assert!(
+                (value - score).abs() < 1e-9,
+                "{}: {} {}",
+                ranking,
+                value,
+                score
+            );
+            assert!(explanation_node(explanation, "weight(").is_some());
+        }
+    }
+
+    // The explanations are only returned when asked for.
+    let body = search(&app, "jaguar cars").await;
+    assert!(body["result"][0].get("explanation").is_none());
+}
+
+/// The explanations detail the stored tf and idf, and the numerator and denominator.
+#[actix_rt::test]
+async fn test_query_explain_details() {
+    let app = spawn_app().await;
+
+    // Act
+    let request = json!({"query": "jaguar", "ranking": "record_cosine", "explain": true});
+    let body: Value = app.post_query(request).await.json().await.unwrap();
+
+    // Assert
+    let explanation = &body["result"][0]["explanation"];
+    let value = |prefix: &str| {
+        explanation_node(explanation, prefix).unwrap()["value"]
+            .as_f64()
+            .unwrap()
+    };
+    let weight = value("weight(jaguar)");
+    assert!(
+        (weight - value("query_weight") * value("tf, stored") * value("idf, stored")).abs() < 1e-9
+    );
+    assert!((value("numerator") - weight).abs() < 1e-9);
+    let score = value("numerator") / (value("query_norm").sqrt() * value("record_norm").sqrt());
+    assert!((explanation["value"].as_f64().unwrap() - score).abs() < 1e-9);
+}
+
+/// The proximity boost is added to the explanations.
+#[actix_rt::test]
+async fn test_query_explain_proximity() {
+    let app = spawn_app_with(|c| c.search.proximity_boost = 100_f64).await;
+
+    // Act
+    let request = json!({"query": "jaguar car", "ranking": "bm25", "explain": true});
+    let body: Value = app.post_query(request).await.json().await.unwrap();
+
+    // Assert
+    let result = body["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["trec_id"] == "clueweb09-en0000-00-00001")
+        .unwrap();
+    let explanation = &result["explanation"];
+    assert_eq!(explanation["description"], "score_mixed, sum of:");
+    let proximity = explanation_node(explanation, "proximity").unwrap();
+    assert!((proximity["value"].as_f64().unwrap() - 100_f64).abs() < 1e-9);
+    let score = result["score_mixed"].as_f64().unwrap();
+    assert!((explanation["value"].as_f64().unwrap() - score).abs() < 1e-9);
 }
@@ -0,0 +1,210 @@
+use crate::app::{fixture, spawn_app, TestApp};
+use crate::query::trec_ids;
+use serde_json::{json, Value};
+use warcse::analysis::AnalyzerKind;
+use warcse::configuration::AnalysisSettings;
+use warcse::services::ingest_corpus;
+
+/// Delete a record, the word statistics must be recomputed.
+#[actix_rt::test]
+async fn test_delete_record() {
+    let app = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Act
+    let response = client
+        .delete(format!("{}/record/clueweb09-en0000-00-00002", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert!(response.status().is_success());
+    let body: Value = app
+        .post_query(json!({"query": "rainforest"}))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        trec_ids(&body),
+        vec!["<urn:uuid:0b8f6b0e-0000-4000-8000-000000000002>"]
+    );
+
+    let word = sqlx::query!(
+        "SELECT total_appearances, frequency FROM word_index WHERE word = 'rainforest'"
+    )
+    .fetch_one(&app.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(word.total_appearances, 2);
+    assert!((word.frequency - 1_f64 / 7_f64).abs() < 1e-9);
+
+    let hunts = sqlx::query!("SELECT word FROM word_index WHERE word = 'hunt'")
+        .fetch_optional(&app.db_pool)
+        .await
+        .unwrap();
+    assert!(hunts.is_none());
+}
+
+/// The cached page is read from the WARC file, with its original content type.
+#[actix_rt::test]
+async fn test_get_record() {
+    let app = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Act
+    let plain = client
+        .get(format!("{}/record/clueweb09-en0000-00-00002", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    let gzip = client
+        .get(format!(
+            "{}/record/%3Curn:uuid:0b8f6b0e-0000-4000-8000-000000000003%3E",
+            &app.address
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert!(plain.status().is_success());
+    assert_eq!(plain.headers()["content-type"], "text/html; charset=utf-8");
+    let body = plain.text().await.unwrap();
+    assert!(body.starts_with("<html><head><title>The Jaguar</title>"));
+    assert!(gzip.status().is_success());
+    assert!(gzip.text().await.unwrap().contains("cars"));
+}
+
+/// Load the records whose bodies are encoded (chunked, compressed).
+pub(crate) async fn ingest_encoded(app: &TestApp) {
+    ingest_corpus(
+        &app.db_pool,
+        "encoded",
+        &[fixture("encoded-sample.warc")],
+        false,
+        AnalyzerKind::English,
+        AnalysisSettings::default().max_body_size,
+    )
+    .await
+    .expect("Failed to load the corpus.");
+}
+
+/// The cached pages are sandboxed, and their chunk framing is removed.
+#[actix_rt::test]
+async fn test_get_record_sandboxed() {
+    let app = spawn_app().await;
+    ingest_encoded(&app).await;
+    let client = reqwest::Client::new();
+
+    // Act
+    let response = client
+        .get(format!(
+            "{}/record/%3Curn:uuid:5c3fdb2a-2222-4222-8222-000000000001%3E",
+            &app.address
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert!(response.status().is_success());
+    assert_eq!(response.headers()["content-security-policy"], "sandbox");
+    assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+    assert!(response.headers().get("transfer-encoding").is_none());
+    let body = response.text().await.unwrap();
+    assert_eq!(
+        body,
+        "<html><head><title>Chunked Page</title></head>\
+         <body><p>Zanzibar spice markets sell cloves.</p></body></html>"
+    );
+}
+
+/// The compressed pages are returned decompressed, also the ones stored decompressed with their
+/// `Content-Encoding`, and the ones with unsupported encodings as they are.
+#[actix_rt::test]
+async fn test_get_record_decoded() {
+    let app = spawn_app().await;
+    ingest_encoded(&app).await;
+    let client = reqwest::Client::new();
+    let get = |id: &str| {
+        client
+            .get(format!(
+                "{}/record/%3Curn:uuid:5c3fdb2a-2222-4222-8222-{}%3E",
+                &app.address, id
+            ))
+            .send()
+    };
+
+    // Act
+    let gzip = get("000000000002")
+        .await
+        .expect("Failed to execute request.");
+    let decompressed = get("000000000004")
+        .await
+        .expect("Failed to execute request.");
+    let brotli = get("000000000003")
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert!(gzip.headers().get("content-encoding").is_none());
+    assert!(gzip
+        .text()
+        .await
+        .unwrap()
+        .contains("<title>Compressed Page</title>"));
+    assert!(decompressed.headers().get("content-encoding").is_none());
+    assert_eq!(
+        decompressed.text().await.unwrap(),
+        "<html><head><title>Decompressed Page</title></head>\
+         <body><p>Nauru phosphate cliffs.</p></body></html>"
+    );
+    assert_eq!(brotli.headers()["content-encoding"], "br");
+}
+
+/// The WARC headers of a record are returned as JSON.
+#[actix_rt::test]
+async fn test_get_record_headers() {
+    let app = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Act
+    let response = client
+        .get(format!(
+            "{}/record/clueweb09-en0000-00-00001/headers",
+            &app.address
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert!(response.status().is_success());
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["version"], "0.18");
+    assert!(body["headers"]
+        .as_array()
+        .unwrap()
+        .contains(&json!({"name": "WARC-TREC-ID", "value": "clueweb09-en0000-00-00001"})));
+}
+
+/// An unknown record has no cached page.
+#[actix_rt::test]
+async fn test_get_record_404() {
+    let app = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Act
+    let response = client
+        .get(format!("{}/record/clueweb09-en0000-00-00042", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["code"], "not_found");
+}
@@ -9,7 +9,7 @@ async fn test_status_200() {
 
     // Act
     let response = client
-        .get(&format!("{}/status", &app.address))
+        .get(format!("{}/status", &app.address))
         .send()
         .await
         .expect("Failed to execute request.");
@@ -18,3 +18,24 @@ async fn test_status_200() {
     assert!(response.status().is_success());
     assert_eq!(Some(0), response.content_length());
 }
+
+/// Perform a request on /stats endpoint.
+/// Should return the counts of the fixture corpora.
+#[actix_rt::test]
+async fn test_stats_200() {
+    let app = spawn_app().await;
+    let client = reqwest::Client::new();
+
+    // Act
+    let response = client
+        .get(format!("{}/stats", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert!(response.status().is_success());
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["corpus_count"], 2);
+    assert_eq!(body["record_count"], 8);
+}
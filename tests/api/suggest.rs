@@ -0,0 +1,130 @@
+use crate::app::{spawn_app, TestApp};
+use serde_json::{json, Value};
+use std::time::Duration;
+use warcse::domain::Completion;
+use warcse::services::load_completions;
+
+/// Request the completions of a prefix.
+async fn suggest(app: &TestApp, parameters: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .get(format!("{}/suggest?{}", &app.address, parameters))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+/// The prefixes are completed with the most frequent indexed words.
+#[actix_rt::test]
+async fn test_suggest() {
+    let app = spawn_app().await;
+
+    // Act
+    let response = suggest(&app, "prefix=Ja").await;
+    let body: Value = response.json().await.unwrap();
+    let frequent: Value = suggest(&app, "prefix=c").await.json().await.unwrap();
+    let limited: Value = suggest(&app, "prefix=c&limit=2")
+        .await
+        .json()
+        .await
+        .unwrap();
+    let unknown: Value = suggest(&app, "prefix=zzz").await.json().await.unwrap();
+
+    // Assert
+    assert_eq!(
+        body,
+        json!({"prefix": "Ja", "completions": [{"word": "jaguar", "appearances": 6}]})
+    );
+    let completions = frequent["completions"].as_array().unwrap();
+    assert_eq!(completions.len(), 5);
+    assert_eq!(completions[0]["word"], "cars");
+    assert!(completions
+        .windows(2)
+        .all(|w| w[0]["appearances"].as_i64() >= w[1]["appearances"].as_i64()));
+    assert_eq!(limited["completions"], json!(completions[..2]));
+    assert_eq!(unknown["completions"], json!([]));
+}
+
+/// A prefix is required.
+#[actix_rt::test]
+async fn test_suggest_without_prefix() {
+    let app = spawn_app().await;
+
+    // Act
+    let response = suggest(&app, "limit=2").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+/// The completions are reloaded when the index is updated, possibly by another process.
+#[actix_rt::test]
+async fn test_suggest_after_ingestion() {
+    let app = spawn_app().await;
+    let before: Value = suggest(&app, "prefix=zeb").await.json().await.unwrap();
+
+    // Act
+    app.ingest_page(
+        "zebras",
+        "http://zebra.example.net/",
+        "<p>Zebras of Kenya</p>",
+    )
+    .await;
+
+    // Assert
+    assert_eq!(before["completions"], json!([]));
+    let mut after = json!([]);
+    for _ in 0..50 {
+        let body: Value = suggest(&app, "prefix=zeb").await.json().await.unwrap();
+        after = body["completions"].clone();
+        if after != json!([]) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(after, json!([{"word": "zebras", "appearances": 1}]));
+}
+
+/// The words are completed with their most frequent spelling, not with their stem.
+#[actix_rt::test]
+async fn test_suggest_spelling() {
+    let app = spawn_app().await;
+    app.ingest_page(
+        "cremes",
+        "http://creme.example.net/",
+        "<p>Happy people love the crème. The Crème is happy, the crèmes are happier.</p>",
+    )
+    .await;
+
+    // Act
+    let completions = load_completions(&app.db_pool, 5)
+        .await
+        .expect("Failed to load the completions.");
+
+    // Assert
+    assert_eq!(
+        completions.complete("hap", 5),
+        [
+            Completion {
+                word: String::from("happy"),
+                appearances: 2
+            },
+            Completion {
+                word: String::from("happier"),
+                appearances: 1
+            }
+        ]
+    );
+    assert_eq!(
+        completions.complete("CREM", 5),
+        [
+            Completion {
+                word: String::from("crème"),
+                appearances: 2
+            },
+            Completion {
+                word: String::from("crèmes"),
+                appearances: 1
+            }
+        ]
+    );
+}
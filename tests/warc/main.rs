@@ -0,0 +1,6 @@
+mod reader;
+
+/// Get the path of a fixture file.
+pub(crate) fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
@@ -0,0 +1,148 @@
+use crate::fixture;
+use std::io::Cursor;
+use warcse::warc::{WarcError, WarcReader, WarcRecord};
+
+/// Read all the records of a fixture file.
+fn read_all(name: &str) -> Vec<WarcRecord> {
+    WarcReader::open(fixture(name))
+        .expect("Failed to open fixture.")
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Failed to parse fixture.")
+}
+
+/// Parse a ClueWeb09 style WARC/0.18 file.
+#[test]
+fn test_read_warc_0_18() {
+    let records = read_all("clueweb09-sample.warc");
+
+    // Assert
+    assert_eq!(records.len(), 6);
+    assert!(records.iter().all(|r| r.version == "0.18"));
+    assert_eq!(records[0].warc_type(), Some("warcinfo"));
+    assert_eq!(records[0].trec_id(), None);
+    assert_eq!(records[1].warc_type(), Some("response"));
+    assert_eq!(records[1].trec_id(), Some("clueweb09-en0000-00-00001"));
+    assert_eq!(
+        records[1].target_uri(),
+        Some("http://www.example.org/jaguar-cars.html")
+    );
+    assert_eq!(
+        records[1].content_length(),
+        records[1]
+            .header("content-length")
+            .unwrap()
+            .parse::<usize>()
+            .unwrap()
+    );
+    assert!(records[1].block.starts_with(b"HTTP/1.1 200 OK\r\n"));
+    assert_eq!(records[5].trec_id(), Some("clueweb09-en0000-00-00005"));
+}
+
+/// Parse a gzip compressed WARC/1.0 file, with one member per record.
+#[test]
+fn test_read_warc_1_0_gzip() {
+    let records = read_all("crawl-sample.warc.gz");
+
+    // Assert
+    assert_eq!(records.len(), 4);
+    assert!(records.iter().all(|r| r.version == "1.0"));
+    let types: Vec<_> = records.iter().map(|r| r.warc_type().unwrap()).collect();
+    assert_eq!(types, vec!["warcinfo", "request", "response", "response"]);
+    assert_eq!(
+        records[2].record_id(),
+        Some("<urn:uuid:0b8f6b0e-0000-4000-8000-000000000002>")
+    );
+    assert_eq!(
+        records[3].target_uri(),
+        Some("http://shop.example.com/cars/new")
+    );
+}
+
+/// Parse a WARC/1.1 file with a bracketed target URI and a folded header.
+#[test]
+fn test_read_warc_1_1() {
+    let records = read_all("example-1.1.warc");
+
+    // Assert
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].version, "1.1");
+    assert_eq!(
+        records[0].target_uri(),
+        Some("https://www.example.org/about")
+    );
+    assert_eq!(
+        records[0].header("X-Crawler-Note"),
+        Some("captured by warcse fixtures")
+    );
+}
+
+/// A record cut in the middle of its block is reported, and the iteration stops.
+#[test]
+fn test_read_truncated_record() {
+    let data = b"WARC/1.0\r\nWARC-Type: resource\r\nContent-Length: 100\r\n\r\nshort".to_vec();
+    let mut reader = WarcReader::new(Cursor::new(data));
+
+    // Assert
+    assert!(matches!(reader.next(), Some(Err(WarcError::Truncated))));
+    assert!(reader.next().is_none());
+}
+
+/// A stream that does not start with a version line is rejected.
+#[test]
+fn test_read_invalid_version() {
+    let mut reader = WarcReader::new(Cursor::new(b"HTTP/1.1 200 OK\r\n\r\n".to_vec()));
+
+    // Assert
+    assert!(matches!(
+        reader.read_record(),
+        Err(WarcError::InvalidVersion(_))
+    ));
+}
+
+/// Every record can be read again from its offset, in plain and compressed files.
+#[test]
+fn test_read_at_offset() {
+    for name in ["clueweb09-sample.warc", "crawl-sample.warc.gz"] {
+        for record in read_all(name) {
+            // Act
+            let read = WarcReader::read_at(fixture(name), record.offset).expect("Failed to read.");
+
+            // Assert
+            assert_eq!(read.headers, record.headers);
+            assert_eq!(read.block, record.block);
+        }
+    }
+    let offsets: Vec<_> = read_all("crawl-sample.warc.gz")
+        .iter()
+        .map(|r| r.offset)
+        .collect();
+    assert!(offsets.iter().all(|offset| offset.member == 0));
+    assert!(offsets.windows(2).all(|w| w[0].file < w[1].file));
+}
+
+/// A record split over two gzip members is read whole, from its offset too.
+#[test]
+fn test_read_at_offset_split_member() {
+    let records = read_all("split-sample.warc.gz");
+
+    // Act
+    let read: Vec<_> = records
+        .iter()
+        .map(|record| {
+            WarcReader::read_at(fixture("split-sample.warc.gz"), record.offset)
+                .expect("Failed to read.")
+        })
+        .collect();
+
+    // Assert
+    let original = read_all("crawl-sample.warc.gz");
+    assert_eq!(records.len(), original.len());
+    for ((read, record), original) in read.iter().zip(records.iter()).zip(original.iter()) {
+        assert_eq!(record.block, original.block);
+        assert_eq!(read.headers, record.headers);
+        assert_eq!(read.block, record.block);
+    }
+    // The split record starts in the first member, the next one in the second.
+    assert_eq!(records[2].offset.file, 0);
+    assert!(records[3].offset.file > 0 && records[3].offset.member > 0);
+}